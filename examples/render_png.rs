@@ -0,0 +1,26 @@
+// renders the scene from renderer_2d::init_renderer once and saves it as a png, no browser needed.
+//     cargo run --example render_png -- [output.png] [width] [height]
+
+use rust_wasm_test_game::{renderer_headless, world::{self, render_world_layers::ImageTransform, world::Object::Objects::{LightObject, WorldObject, WorldObjectData}}};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| "frame.png".to_string());
+    let width = args.next().map(|v| v.parse().expect("width must be a number")).unwrap_or(1600);
+    let height = args.next().map(|v| v.parse().expect("height must be a number")).unwrap_or(900);
+
+    let mut world_renderer = world::render_world::WorldRenderer::new(world::world::World::new(1600f32/9f32, 100f32), width, height);
+
+    world_renderer.world.lights_rendered.push(LightObject::new(0.0, 0.0, (50000, 50000, 50000), 50.0, 50.0));
+    world_renderer.world.lights_rendered.push(LightObject::new(0.0, -100.0, (00000, 50000, 50000), 25.0, 50.0));
+    world_renderer.world.lights_rendered.push(LightObject::new(0.0, 100.0, (50000, 20000, 20000), 25.0, 75.0));
+    {
+        let img = rust_wasm_test_game::assets::image_loader_hardcoded::get_image1_raw_bytes();
+        world_renderer.world.objects_rendered.push(WorldObject::new_rel(WorldObjectData::Image { rgba: img.0, width: img.1, height: img.2, transform: ImageTransform::default() }, 0.1, 0.1, 0.8, 0.8, width, height));
+    }
+
+    world_renderer.init();
+
+    let durations = renderer_headless::render_to_png(&mut world_renderer, &path).expect("saving the frame");
+    println!("Took {}+{}+{}ms to render {}x{}px to {}", durations[0].as_millis(), durations[1].as_millis(), durations[2].as_millis(), width, height, path);
+}
@@ -1 +1,5 @@
-import("../pkg/index.js").catch(console.error);
\ No newline at end of file
+// main_js only starts the renderer once rayon's thread pool is running, see index.html
+import("../pkg/index.js").then(async (wasm) => {
+  await wasm.initThreadPool(navigator.hardwareConcurrency);
+  wasm.main_js();
+}).catch(console.error);
\ No newline at end of file
@@ -1,2 +1,3 @@
 pub mod mouse;
-pub mod keyboard;
\ No newline at end of file
+pub mod keyboard;
+pub mod window;
\ No newline at end of file
@@ -0,0 +1,29 @@
+use gloo::events::EventListener;
+use web_sys::Window;
+use std::sync::mpsc::Sender;
+
+use crate::renderer::Interactions;
+
+/// the size of the window in device pixels (css pixels times devicePixelRatio), which is how big the canvas has to be to look sharp
+pub fn size_in_device_pixels(window: &Window) -> (u32, u32) {
+    let pixel_ratio = window.device_pixel_ratio();
+    let width = window.inner_width().unwrap().as_f64().unwrap() * pixel_ratio;
+    let height = window.inner_height().unwrap().as_f64().unwrap() * pixel_ratio;
+    (width.round() as u32, height.round() as u32)
+}
+/// the window was resized, or devicePixelRatio changed (see listen_for_pixel_ratio_changes)
+pub fn resized(window: &Window, sender: Sender<Interactions>) {
+    let (width, height) = size_in_device_pixels(window);
+    // nothing to do if the renderer is gone
+    let _ = sender.send(Interactions::Resize { width, height, });
+}
+/// calls resized when devicePixelRatio changes (the window is moved to another screen, or the page is zoomed), which doesn't always fire "resize"
+pub fn listen_for_pixel_ratio_changes(window: Window, sender: Sender<Interactions>) {
+    // matchMedia only throws for queries it can't parse
+    let Ok(Some(query)) = window.match_media(&format!("(resolution: {}dppx)", window.device_pixel_ratio())) else { return };
+    EventListener::once(&query, "change", move |_| {
+        resized(&window, sender.clone());
+        // the query was for the old ratio, so the next change needs a query for the new one
+        listen_for_pixel_ratio_changes(window, sender);
+    }).forget();
+}
@@ -8,9 +8,10 @@ use web_sys::HtmlCanvasElement;
 const RCID: &str = "2d"; mod renderer_2d; use renderer_2d as renderer; use web_sys::CanvasRenderingContext2d as RenderingContext;
 //
 
-mod world;
-mod assets;
+pub mod world;
+pub mod assets;
 mod interactions;
+pub mod renderer_headless;
 
 
 // When the `wee_alloc` feature is enabled, this uses `wee_alloc` as the global
@@ -22,8 +23,14 @@ mod interactions;
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 
+// starts rayon's thread pool on the web, initThreadPool in js. it has to be awaited before main_js, see index.html.
+#[cfg(target_arch = "wasm32")]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
 // This is like the `main` function, except for JavaScript.
-#[wasm_bindgen(start)]
+// it is called from js once the thread pool is running (see index.html), not when the module is loaded, because rayon can't start its
+// pool again once it has been used.
+#[wasm_bindgen]
 pub fn main_js() -> Result<(), JsValue> {
     // This provides better error messages in debug mode.
     // It's disabled in release mode so it doesn't bloat up the file size.
@@ -39,18 +46,11 @@ pub fn main_js() -> Result<(), JsValue> {
 
     document.set_title("Loading...");
 
-    let mut pixel_width_f: f64 = 0.0;
-    let mut pixel_height_f: f64 = 0.0;
-    let mut pixel_width_i: u32 = 0;
-    let mut pixel_height_i: u32 = 0;
-    let mut update_canvas_size = || {
-        pixel_width_f = window.inner_width().unwrap().as_f64().unwrap();
-        pixel_height_f = window.inner_height().unwrap().as_f64().unwrap();
-        pixel_width_i = pixel_width_f.round() as u32; pixel_height_i = pixel_height_f.round() as u32;
-        canvas.set_width(pixel_width_i);
-        canvas.set_height(pixel_height_i);
-    };
-    update_canvas_size();
+    // the canvas fills the window (see index.html) and has one pixel per device pixel, so it is sharp on hidpi screens.
+    // when the window is resized, renderer_2d changes it again.
+    let (pixel_width_i, pixel_height_i) = interactions::window::size_in_device_pixels(&window);
+    canvas.set_width(pixel_width_i);
+    canvas.set_height(pixel_height_i);
 
     document.set_title("Started.");
 
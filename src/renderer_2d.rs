@@ -3,7 +3,7 @@ use wasm_bindgen::{closure::Closure, JsCast};
 use web_sys::{CanvasRenderingContext2d as RenderingContext, Performance, Window, Document};
 use std::{rc::Rc, cell::RefCell, time::Duration, collections::VecDeque, sync::mpsc::{self, Sender, Receiver}};
 
-use crate::{world::{self, world::Object::Objects::{WorldObject, WorldObjectData}, render_world_layers::Pixel}};
+use crate::{world::{self, world::Object::Objects::{WorldObject, WorldObjectData}, render_world_layers::{ImageTransform, Pixel}}};
 
 pub struct ImpInfo {
     pub context: RenderingContext,
@@ -20,13 +20,13 @@ pub struct ImpInfo {
 pub enum Interactions {
     MouseDown { button: i16, x: i32, y: i32, },
     MouseMove { button: i16, x: i32, y: i32, },
+    /// the new size of the window in device pixels
+    Resize { width: u32, height: u32, },
 }
 
 fn render(info: &mut ImpInfo) {
     let time_start = wasm_timer::Instant::now();
 
-    let width = info.width;
-    let height = info.height;
     let window_width_f = info.window.inner_width().unwrap().as_f64().unwrap() as f32;
     let window_height_f = info.window.inner_height().unwrap().as_f64().unwrap() as f32;
     let image_bytes = &mut info.image_bytes;
@@ -35,19 +35,6 @@ fn render(info: &mut ImpInfo) {
     let document = &info.document;
     let message_receiver = &info.message_receiver;
 
-    // if the data vec is too long or too short, adjust its size.
-    let byte_length = (width * height * 4) as usize;
-    if image_bytes.len() != byte_length {
-        let mut len = image_bytes.len();
-        while len > byte_length {
-            image_bytes.pop();
-            len -= 1;
-        }
-        while len < byte_length {
-            image_bytes.push(255u8);
-            len += 1;
-        }
-    }
     // handle channel
     loop {
         match message_receiver.try_recv() {
@@ -59,6 +46,15 @@ fn render(info: &mut ImpInfo) {
                         light.x = (-1.0 + 2.0 * x as f32 / window_width_f) * world_renderer.world.width;
                         light.y = (-1.0 + 2.0 * y as f32 / window_height_f) * world_renderer.world.height;
                     },
+                    Interactions::Resize { width, height } => {
+                        if let Some(canvas) = context.canvas() {
+                            canvas.set_width(width);
+                            canvas.set_height(height);
+                        }
+                        world_renderer.resize(width as usize, height as usize);
+                        info.width = width as usize;
+                        info.height = height as usize;
+                    },
                 }
             },
             Err(_) => {
@@ -67,6 +63,22 @@ fn render(info: &mut ImpInfo) {
         }
     }
 
+    // after a resize, the new size is used from this frame on
+    let width = info.width;
+    let height = info.height;
+    // if the data vec is too long or too short, adjust its size.
+    let byte_length = (width * height * 4) as usize;
+    if image_bytes.len() != byte_length {
+        let mut len = image_bytes.len();
+        while len > byte_length {
+            image_bytes.pop();
+            len -= 1;
+        }
+        while len < byte_length {
+            image_bytes.push(255u8);
+            len += 1;
+        }
+    }
     // OTHER
     /*
     world_renderer.world.renderable.lights_rendered[0].x += 5.0;
@@ -87,9 +99,11 @@ fn render(info: &mut ImpInfo) {
     {
         durations = world_renderer.render(image_bytes);
     }
-    // put image data
-    let image_data = web_sys::ImageData::new_with_u8_clamped_array(wasm_bindgen::Clamped(image_bytes), width as u32).unwrap();
-    match context.put_image_data(&image_data, 0.0, 0.0) { Ok(_) => {}, Err(_) => {}, }
+    // put image data. ImageData can't be empty, and there is nothing to show on a canvas without pixels anyway.
+    if width > 0 && height > 0 {
+        let image_data = web_sys::ImageData::new_with_u8_clamped_array(wasm_bindgen::Clamped(image_bytes), width as u32).unwrap();
+        match context.put_image_data(&image_data, 0.0, 0.0) { Ok(_) => {}, Err(_) => {}, }
+    }
     let time_render = time_start.elapsed();
     document.set_title(format!("Took {}={}+{}+{}ms to render {}x{}px", time_render.as_millis(), durations[0].as_millis(), durations[1].as_millis(), durations[2].as_millis(), width, height).as_str());
 }
@@ -113,7 +127,12 @@ pub fn init_renderer(gl: RenderingContext, width: usize, height: usize, window:
         //world_renderer.world.objects_rendered.push(WorldObject::new_rel(WorldObjectData::Rectangle { color: Pixel::Opaque { r: 255, g: 127, b: 0 } }, 0.0, 0.25, 0.5, 0.5, width, height));
         //world_renderer.world.objects_rendered.push(WorldObject::new_rel(WorldObjectData::Rectangle { color: Pixel::Opaque { r: 0, g: 127, b: 255 } }, 0.5, 0.25, 0.5, 0.5, width, height));
         //world_renderer.world.objects_rendered.push(WorldObject::new_rel(WorldObjectData::Rectangle { color: Pixel::Opaque { r: 255, g: 127, b: 0 } }, 0.0, 0.25, 0.5, 0.5, width, height));
-        world_renderer.world.objects_rendered.push(WorldObject::new_rel(WorldObjectData::Image { rgba: img.0, width: img.1, height: img.2 }, 0.1, 0.1, 0.8, 0.8, width, height))
+        let mut image = WorldObject::new_rel(WorldObjectData::Image { rgba: img.0, width: img.1, height: img.2, transform: ImageTransform::default() }, 0.1, 0.1, 0.8, 0.8, width, height);
+        // the demo image slides to the right by a pixel every 10ms and jumps back every second
+        image.fns.draw_again = Box::new(|state, duration| {
+            state.layer.pos_x = state.layer.pos_x_start + ((duration.as_millis() % 1000) / 10) as f32;
+        });
+        world_renderer.world.objects_rendered.push(image);
     }
 
     world_renderer.init();
@@ -146,6 +165,10 @@ pub fn init_renderer(gl: RenderingContext, width: usize, height: usize, window:
         EventListener::new(&imp_info.document, "mousedown", move |event| {crate::interactions::mouse::down(event.to_owned(), sender.clone());}).forget();
         let sender = imp_info.message_sender.clone();
         EventListener::new(&imp_info.document, "mousemove", move |event| {crate::interactions::mouse::moved(event.to_owned(), sender.clone());}).forget();
+        let sender = imp_info.message_sender.clone();
+        let window = imp_info.window.clone();
+        EventListener::new(&imp_info.window, "resize", move |_| {crate::interactions::window::resized(&window, sender.clone());}).forget();
+        crate::interactions::window::listen_for_pixel_ratio_changes(imp_info.window.clone(), imp_info.message_sender.clone());
     }
 
     *outer_f.borrow_mut() = Some(Closure::wrap(Box::new(move || {
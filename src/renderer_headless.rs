@@ -0,0 +1,22 @@
+use std::{path::Path, time::Duration};
+
+use image::{ImageResult, RgbaImage};
+
+use crate::world::render_world::WorldRenderer;
+
+/// renders one frame without a browser. the returned image has the same size as the world_renderer.
+pub fn render_to_image(world_renderer: &mut WorldRenderer) -> (RgbaImage, [Duration; 3]) {
+    // the joiner never writes the alpha channel, so the buffer has to start out opaque (like image_bytes in renderer_2d).
+    let mut image_bytes = vec![255u8; world_renderer.width * world_renderer.height * 4];
+    let durations = world_renderer.render(&mut image_bytes);
+    let image = RgbaImage::from_raw(world_renderer.width as u32, world_renderer.height as u32, image_bytes)
+        .expect("image_bytes has exactly width * height * 4 bytes");
+    (image, durations)
+}
+
+/// renders one frame without a browser and saves it to a png file at path.
+pub fn render_to_png<P: AsRef<Path>>(world_renderer: &mut WorldRenderer, path: P) -> ImageResult<[Duration; 3]> {
+    let (image, durations) = render_to_image(world_renderer);
+    image.save_with_format(path, image::ImageFormat::Png)?;
+    Ok(durations)
+}
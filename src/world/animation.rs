@@ -0,0 +1,120 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// an image with many frames in it, see WorldObjectData::SpriteSheet. one sheet can be used by many objects.
+pub struct SpriteSheet {
+    /// not premultiplied, like in png files
+    pub rgba: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+    /// (x, y, w, h) of each frame in the image, in pixels
+    pub frames: Vec<(usize, usize, usize, usize)>,
+    pub clips: HashMap<String, AnimationClip>,
+} impl SpriteSheet {
+    /// a sheet without clips whose frames are a grid of frame_w x frame_h rectangles, line by line.
+    pub fn grid(rgba: Vec<u8>, width: usize, height: usize, frame_w: usize, frame_h: usize) -> Self {
+        let mut frames = vec![];
+        for y in 0..height / frame_h {
+            for x in 0..width / frame_w {
+                frames.push((x * frame_w, y * frame_h, frame_w, frame_h));
+            }
+        }
+        Self { rgba, width, height, frames, clips: HashMap::new() }
+    }
+    pub fn with_clip(mut self, name: &str, clip: AnimationClip) -> Self {
+        self.clips.insert(name.to_string(), clip);
+        self
+    }
+}
+
+/// what happens when a clip reaches its last frame
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoopMode {
+    /// stay on the last frame
+    Once,
+    /// start again at the first frame
+    Loop,
+    /// play backwards to the first frame, then forwards again
+    PingPong,
+}
+
+pub struct AnimationClip {
+    /// indices into SpriteSheet.frames and how long each of them is shown
+    pub frames: Vec<(usize, Duration)>,
+    pub loop_mode: LoopMode,
+} impl AnimationClip {
+    pub fn new(frames: Vec<(usize, Duration)>, loop_mode: LoopMode) -> Self {
+        Self { frames, loop_mode }
+    }
+    /// a clip that shows each of frames for frame_duration
+    pub fn uniform(frames: impl IntoIterator<Item = usize>, frame_duration: Duration, loop_mode: LoopMode) -> Self {
+        Self::new(frames.into_iter().map(|frame| (frame, frame_duration)).collect(), loop_mode)
+    }
+    /// the frame (index into SpriteSheet.frames) which is shown after the clip has played for elapsed
+    pub fn frame_at(&self, elapsed: Duration) -> usize {
+        // the positions in frames in the order they are shown, once
+        let order: Vec<usize> = match self.loop_mode {
+            LoopMode::Once | LoopMode::Loop => (0..self.frames.len()).collect(),
+            LoopMode::PingPong => (0..self.frames.len()).chain((1..self.frames.len().saturating_sub(1)).rev()).collect(),
+        };
+        let total: Duration = order.iter().map(|&i| self.frames[i].1).sum();
+        let Some(&last) = order.last() else { return 0 };
+        if total.is_zero() || (self.loop_mode == LoopMode::Once && elapsed >= total) {
+            return self.frames[last].0;
+        }
+        let mut time = Duration::from_nanos((elapsed.as_nanos() % total.as_nanos()) as u64);
+        for &i in order.iter() {
+            if time < self.frames[i].1 {
+                return self.frames[i].0;
+            }
+            time -= self.frames[i].1;
+        }
+        self.frames[last].0
+    }
+}
+
+/// which clip of a sprite sheet an object plays, changed at runtime with play and stop.
+/// the times are from the World clock (World.start_time), which draw_again gets.
+pub struct AnimationState {
+    pub sheet: Arc<SpriteSheet>,
+    clip: Option<String>,
+    playing: bool,
+    /// when the clip was started, None until the next frame is drawn after play
+    started_at: Option<Duration>,
+    /// the frame which is in the layer right now
+    pub(crate) shown_frame: Option<usize>,
+    /// the frame which is shown when no clip is playing
+    frame: usize,
+} impl AnimationState {
+    pub fn new(sheet: Arc<SpriteSheet>) -> Self {
+        Self { sheet, clip: None, playing: false, started_at: None, shown_frame: None, frame: 0 }
+    }
+    /// plays the clip called name from its start. if it is already playing, it just keeps playing.
+    /// to switch clips, play another one.
+    pub fn play(&mut self, name: &str) {
+        if self.playing && self.clip.as_deref() == Some(name) { return; }
+        self.clip = Some(name.to_string());
+        self.playing = true;
+        self.started_at = None;
+    }
+    /// stays on the frame that is shown right now
+    pub fn stop(&mut self) {
+        self.playing = false;
+    }
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+    /// the name of the clip that is playing or was played last
+    pub fn clip(&self) -> Option<&str> {
+        self.clip.as_deref()
+    }
+    /// the frame (index into sheet.frames) that should be shown at now (on the World clock)
+    pub fn frame_at(&mut self, now: Duration) -> usize {
+        if self.playing {
+            if let Some(clip) = self.clip.as_ref().and_then(|clip| self.sheet.clips.get(clip)) {
+                let started_at = *self.started_at.get_or_insert(now);
+                self.frame = clip.frame_at(now.saturating_sub(started_at));
+            }
+        }
+        self.frame
+    }
+}
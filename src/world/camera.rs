@@ -0,0 +1,136 @@
+/// the part of the world that is shown on the screen. lights, occluders and objects placed in the world (see WorldObject::in_world)
+/// are in world units and go through the camera, objects placed on the screen (new_rel, new_abs) don't.
+///
+/// without a camera (or with the default one), the screen shows -world.width..world.width from left to right and
+/// -world.height..world.height from top to bottom. these are called view coordinates here.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Camera {
+    /// the point in world units that is in the centre of the screen
+    pub x: f32,
+    pub y: f32,
+    /// how much bigger everything is shown, 2 shows half as much of the world
+    pub zoom: f32,
+    /// the world is turned by this much (clockwise, in radians) around the centre of the screen.
+    /// this only looks right if world.width / world.height is the aspect ratio of the screen.
+    pub rotation: f32,
+    /// added to x, y and rotation by screen shake (see CameraMotion), without moving the camera itself
+    pub shake: (f32, f32, f32),
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self { x: 0.0, y: 0.0, zoom: 1.0, rotation: 0.0, shake: (0.0, 0.0, 0.0) }
+    }
+}
+
+impl Camera {
+    /// where (x, y) in world units is shown, in view coordinates
+    pub fn world_to_view(&self, x: f32, y: f32) -> (f32, f32) {
+        let (sin, cos) = self.rotation().sin_cos();
+        let (dx, dy) = ((x - self.x - self.shake.0) * self.zoom, (y - self.y - self.shake.1) * self.zoom);
+        (dx * cos - dy * sin, dx * sin + dy * cos)
+    }
+    /// the point in world units that is shown at (x, y) in view coordinates
+    pub fn view_to_world(&self, x: f32, y: f32) -> (f32, f32) {
+        let (sin, cos) = (-self.rotation()).sin_cos();
+        ((x * cos - y * sin) / self.zoom + self.x + self.shake.0, (x * sin + y * cos) / self.zoom + self.y + self.shake.1)
+    }
+    /// the rotation the world is shown with, including screen shake
+    pub fn rotation(&self) -> f32 {
+        self.rotation + self.shake.2
+    }
+    /// where (x, y) in world units is on a screen of screen_w x screen_h pixels, for a world of world_w x world_h (World.width and World.height).
+    /// this is the same mapping the light map uses.
+    pub fn world_to_screen(&self, (x, y): (f32, f32), (world_w, world_h): (f32, f32), (screen_w, screen_h): (usize, usize)) -> (f32, f32) {
+        let (view_x, view_y) = self.world_to_view(x, y);
+        ((view_x / world_w + 1.0) * (screen_w as f32 - 1.0) / 2.0, (view_y / world_h + 1.0) * (screen_h as f32 - 1.0) / 2.0)
+    }
+    /// the point in world units that is at pixel (x, y) of the screen, the inverse of world_to_screen
+    pub fn screen_to_world(&self, (x, y): (f32, f32), (world_w, world_h): (f32, f32), (screen_w, screen_h): (usize, usize)) -> (f32, f32) {
+        let view_x = (x * 2.0 / (screen_w as f32 - 1.0) - 1.0) * world_w;
+        let view_y = (y * 2.0 / (screen_h as f32 - 1.0) - 1.0) * world_h;
+        self.view_to_world(view_x, view_y)
+    }
+}
+
+/// moves World.camera every frame (see World::update_camera): it follows an object, stays inside of the level and shakes.
+/// all distances are in world units.
+pub struct CameraMotion {
+    /// the index of the object in world.objects_rendered that the camera follows. it has to be placed in the world (see WorldObject::in_world).
+    pub follow: Option<usize>,
+    /// how long (in seconds) the camera takes to get about two thirds of the way to where it should be. 0 doesn't smooth anything.
+    pub smoothing: f32,
+    /// half the width and height of a box around the centre of the screen that the target can move in without moving the camera
+    pub dead_zone: (f32, f32),
+    /// (min_x, min_y, max_x, max_y) of the level. the camera doesn't show anything outside of it, unless it is rotated or shaking.
+    /// if the level is smaller than the screen, it is kept in the centre.
+    pub bounds: Option<(f32, f32, f32, f32)>,
+    /// how much the screen shakes right now, from 0 to 1. the shake grows with trauma squared, so small hits only shake a little.
+    pub trauma: f32,
+    /// how much trauma goes away per second
+    pub trauma_decay: f32,
+    /// the most the camera is moved (in world units) and turned (in radians) by shaking at a trauma of 1
+    pub max_shake_offset: f32,
+    pub max_shake_angle: f32,
+    /// how quickly the screen shakes, about how many times per second
+    pub shake_frequency: f32,
+    /// seconds since the motion started, the shake is different at each point in time
+    pub time: f32,
+}
+
+impl Default for CameraMotion {
+    fn default() -> Self {
+        Self {
+            follow: None,
+            smoothing: 0.0,
+            dead_zone: (0.0, 0.0),
+            bounds: None,
+            trauma: 0.0,
+            trauma_decay: 1.0,
+            max_shake_offset: 1.0,
+            max_shake_angle: 0.05,
+            shake_frequency: 15.0,
+            time: 0.0,
+        }
+    }
+}
+
+/// smooth noise from -1 to 1, a different one for each seed
+fn shake_noise(t: f32, seed: f32) -> f32 {
+    (t + seed).sin() * 0.6 + (t * 2.3 + seed * 1.9).sin() * 0.4
+}
+
+impl CameraMotion {
+    /// shakes the screen more, for example when something explodes. trauma never goes above 1.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+    /// moves camera towards target (in world units, the point it follows) for a frame that took dt seconds.
+    /// view_size is half the width and height of what the screen shows at a zoom of 1 (World.width and World.height).
+    pub fn update(&mut self, camera: &mut Camera, target: Option<(f32, f32)>, dt: f32, view_size: (f32, f32)) {
+        self.time += dt;
+        if let Some((target_x, target_y)) = target {
+            // only as far as the target is outside of the dead zone
+            let goal = |position: f32, target: f32, dead_zone: f32| position + (target - position).signum() * ((target - position).abs() - dead_zone).max(0.0);
+            let (goal_x, goal_y) = (goal(camera.x, target_x, self.dead_zone.0), goal(camera.y, target_y, self.dead_zone.1));
+            let t = if self.smoothing > 0.0 { 1.0 - (-dt / self.smoothing).exp() } else { 1.0 };
+            camera.x += (goal_x - camera.x) * t;
+            camera.y += (goal_y - camera.y) * t;
+        }
+        if let Some((min_x, min_y, max_x, max_y)) = self.bounds {
+            let keep_inside = |position: f32, min: f32, max: f32, half_view: f32| {
+                if max - min <= 2.0 * half_view { (min + max) / 2.0 } else { position.clamp(min + half_view, max - half_view) }
+            };
+            camera.x = keep_inside(camera.x, min_x, max_x, view_size.0 / camera.zoom);
+            camera.y = keep_inside(camera.y, min_y, max_y, view_size.1 / camera.zoom);
+        }
+        let shake = self.trauma * self.trauma;
+        let t = self.time * self.shake_frequency;
+        camera.shake = if shake > 0.0 {
+            (self.max_shake_offset * shake * shake_noise(t, 0.0), self.max_shake_offset * shake * shake_noise(t, 10.0), self.max_shake_angle * shake * shake_noise(t, 20.0))
+        } else {
+            (0.0, 0.0, 0.0)
+        };
+        self.trauma = (self.trauma - self.trauma_decay * dt).max(0.0);
+    }
+}
@@ -1,3 +1,9 @@
+pub mod animation;
+pub mod camera;
 pub mod render_world;
 pub mod render_world_layers;
+pub mod render_world_simd;
+pub mod shapes;
+pub mod text;
+pub mod tilemap;
 pub mod world;
\ No newline at end of file
@@ -1,6 +1,8 @@
 use std::time::Duration;
 
-use super::world::World;
+use rayon::prelude::*;
+
+use super::{world::{World, RenderLayer, Object::Objects::{WorldObject, WorldObjectData}}, render_world_layers::{Emission, Pixel}};
 
 
 
@@ -10,6 +12,24 @@ pub struct WorldRenderer {
     pub height: usize,
     pub lights_renderer: LightMap,
     pub objects_renderer: ObjectNoLightRenderer,
+    /// how the light map is sampled when it is joined with the objects
+    pub join_mode: render_joiner::JoinMode,
+    /// how light brighter than full brightness (from overlapping lights) is shown, see render_joiner::ToneMapping
+    pub tone_mapping: render_joiner::ToneMapping,
+    /// light the objects in linear colour space instead of multiplying their srgb values, see render_joiner::ToneCurve.
+    /// dim light doesn't make colours darker than they should be and doesn't change their hue.
+    pub linear_lighting: bool,
+    /// the lookup tables for tone_mapping and linear_lighting, made again by render when one of them changes
+    tone_curve: render_joiner::ToneCurve,
+    /// split the light map, layer composition and join into bands of rows which run on rayon's thread pool.
+    /// the result is exactly the same as with parallel = false.
+    pub parallel: bool,
+    /// use the simd versions of the light map and join loops (see render_world_simd). the result is exactly the same as with simd = false.
+    pub simd: bool,
+    /// when the last frame was rendered, to move the camera by the time in between
+    last_frame: Option<wasm_timer::Instant>,
+    /// the last screen size bigger than 1 x 1 pixels, which world.width fits (see resize)
+    world_fits: (usize, usize),
 } impl WorldRenderer {
     pub fn new(world: super::world::World, width: usize, height: usize) -> Self {
         Self {
@@ -18,25 +38,76 @@ pub struct WorldRenderer {
             height: height,
             lights_renderer: LightMap::new(width, height, 2),
             objects_renderer: ObjectNoLightRenderer::new(width, height),
+            join_mode: render_joiner::JoinMode::Nearest,
+            tone_mapping: render_joiner::ToneMapping::Clamp,
+            linear_lighting: false,
+            tone_curve: render_joiner::ToneCurve::new(render_joiner::ToneMapping::Clamp, false),
+            parallel: true,
+            simd: true,
+            last_frame: None,
+            world_fits: (width, height),
         }
     }
 
+    /// changes the size of a light map cell to inaccuracy x inaccuracy pixels. bigger values are faster, but less detailed.
+    pub fn set_inaccuracy(&mut self, inaccuracy: usize) {
+        self.lights_renderer = LightMap::new(self.width, self.height, inaccuracy);
+    }
+
     pub fn init(&mut self) {
         self.objects_renderer.draw_init(&mut self.world);
     }
 
+    /// changes the size of the screen to width x height pixels, for example when the window is resized (in device pixels, so the picture stays sharp on hidpi screens).
+    /// the light map and buffers are made again and all objects are drawn again: objects placed with new_rel keep their place on the screen,
+    /// objects placed with new_abs keep their position in pixels and objects in the world keep their size in the world.
+    /// the world keeps its height, its width changes with the aspect ratio of the screen so nothing is stretched more than before.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if (width, height) == (self.width, self.height) { return; }
+        // screens of a single row or column (or nothing at all, like on a hidden page) have no aspect ratio, the world is kept as it is for them
+        if width > 1 && height > 1 {
+            let aspect_ratio = |(width, height): (usize, usize)| (width - 1) as f32 / (height - 1) as f32;
+            if self.world_fits.0 > 1 && self.world_fits.1 > 1 {
+                self.world.width *= aspect_ratio((width, height)) / aspect_ratio(self.world_fits);
+            }
+            self.world_fits = (width, height);
+        }
+        self.width = width;
+        self.height = height;
+        self.lights_renderer = LightMap::new(width, height, self.lights_renderer.inaccuracy);
+        self.objects_renderer = ObjectNoLightRenderer::new(width, height);
+        for object in self.world.objects_rendered.iter_mut() {
+            object.state.resize_screen(width, height);
+        }
+        self.init();
+    }
+
     pub fn render(&mut self, image_data: &mut Vec<u8>) -> [Duration; 3] {
+        let now = wasm_timer::Instant::now();
+        self.world.update_camera(self.last_frame.map_or(Duration::ZERO, |last_frame| now - last_frame));
+        self.last_frame = Some(now);
+        // a hidden page can have a screen without any pixels
+        if self.width == 0 || self.height == 0 { return [Duration::ZERO; 3]; }
+
         // draw objects to Vec<Layer>
         let start_time = wasm_timer::Instant::now();
-        self.objects_renderer.draw_all(&mut self.world);
+        self.objects_renderer.draw_all(&mut self.world, self.parallel);
         let elapsed_time_objects = start_time.elapsed();
         
-        // draw light/brightness to Vec<(u16, u16, u16)>
+        // draw light/brightness to Vec<(u32, u32, u32)>
         let start_time = wasm_timer::Instant::now();
-        self.lights_renderer.calculate(&self.world, self.width, self.height);
+        // the lights of glowing objects (see WorldObject::with_emitted_light) are only there for this frame
+        let lights = self.world.lights_rendered.len();
+        let emitted_lights = self.world.emitted_lights();
+        self.world.lights_rendered.extend(emitted_lights);
+        self.lights_renderer.calculate(&self.world, self.width, self.height, self.parallel, self.simd);
+        self.world.lights_rendered.truncate(lights);
         let elapsed_time_brightness = start_time.elapsed();
 
         let start_time = wasm_timer::Instant::now();
+        if !self.tone_curve.is_for(self.tone_mapping, self.linear_lighting) {
+            self.tone_curve = render_joiner::ToneCurve::new(self.tone_mapping, self.linear_lighting);
+        }
         super::render_world::render_joiner::join(image_data, self);
         let elapsed_time_join = start_time.elapsed();
 
@@ -50,69 +121,101 @@ pub struct LightMap {
     width: usize,
     height: usize,
     inaccuracy: usize,
-    data: Vec<(u16, u16, u16)>, // brightness in (rgb) format
+    data: Vec<(u32, u32, u32)>, // brightness in (rgb) format, u16::MAX is full brightness and the most is MAX_LIGHT
+    /// for each tile of LIGHT_TILE_SIZE x LIGHT_TILE_SIZE cells, the indices of the lights that can reach it. refilled by calculate.
+    tiles: Vec<Vec<usize>>,
 } impl LightMap {
+    /// a light map for a w x h screen. it has at least 2 x 2 cells, even if the screen is smaller than that (or empty), because the
+    /// first cell is at the first pixel and the last cell at the last pixel.
     pub fn new(w: usize, h: usize, inaccuracy: usize) -> Self {
-        let w = w / inaccuracy;
-        let h = h / inaccuracy;
+        let w = (w / inaccuracy).max(2);
+        let h = (h / inaccuracy).max(2);
         let len = w * h;
         Self {
             width: w,
             height: h,
             inaccuracy: inaccuracy,
-            data: vec![(0, 0, 0); len]
+            data: vec![(0, 0, 0); len],
+            tiles: vec![Vec::new(); w.div_ceil(LIGHT_TILE_SIZE) * h.div_ceil(LIGHT_TILE_SIZE)],
         }
     }
-    pub fn calculate(&mut self, world: &super::world::World, width: usize, height: usize) {
-        {
-
-            let mut index = 0;
-            for y in 0..self.height {
-                let Y = ((y * 2) as f32 / (self.height - 1) as f32 - 1f32) * world.height; // convert the value from pixels to a relative value from -1 to 1
-                for x in 0..self.width {
-                    let X = ((x * 2) as f32 / (self.width - 1) as f32 - 1f32) * world.width; // convert the value from pixels to a relative value from -1 to 1
-                    self.data[index] = {
-                        let mut r: u16 = 0;
-                        let mut g: u16 = 0;
-                        let mut b: u16 = 0;
-                        for light_source in &world.lights_rendered {
-                            let dist_x = light_source.x - X;
-                            let dist_y = light_source.y - Y;
-
-                            let light_source_max_reach = light_source.size + light_source.range;
-                            let light_source_max_reach_squared = light_source_max_reach * light_source_max_reach;
-
-                            if dist_x > light_source_max_reach || dist_y > light_source_max_reach { continue; }
-
-                            let dist_squared = dist_x * dist_x + dist_y * dist_y; // don't use sqrt because it's rather slow
-
-                            if dist_squared >= light_source_max_reach_squared { continue; }
-
-                            let light_source_size_squared = light_source.size * light_source.size;
-
-                            if light_source_size_squared >= dist_squared {
-                                r = r.saturating_add(light_source.brightness.0);
-                                g = g.saturating_add(light_source.brightness.1);
-                                b = b.saturating_add(light_source.brightness.2);
-                            } else {
-                                let factor_at_size = light_source_size_squared / light_source_max_reach_squared;
-                                let factor = dist_squared / light_source_max_reach_squared; // the sqrt of this factor is the actual factor. The factor is always less than 1.
-                                let factor = (1.0 - factor) /* the width of the outer ring */ / (1.0 - factor_at_size) /* the maximum size of the outer ring (i.e. the value that 1-f1 will have at its insidemost point) */;
-                                let factor = factor * factor; // this just makes it look a bit nicer, there should be almost no performance impact
-                                let factor_int = (factor * u16::MAX as f32) as u32;
-                                r = r.saturating_add(((light_source.brightness.0 as u32 * factor_int) >> 16) as u16);
-                                g = g.saturating_add(((light_source.brightness.1 as u32 * factor_int) >> 16) as u16);
-                                b = b.saturating_add(((light_source.brightness.2 as u32 * factor_int) >> 16) as u16);
-                            };
-                        };
-                        (r, g, b)
-                    };
-                    self.data[index];
-                    index += 1;
+    /// the indices (in lights_rendered) of the lights that calculate found can reach the tile with pixel (x, y) of the screen in it
+    #[doc(hidden)]
+    pub fn lights_in_tile_at(&self, x: usize, y: usize) -> &[usize] {
+        let tiles_x = self.width.div_ceil(LIGHT_TILE_SIZE);
+        let (cell_x, cell_y) = ((x / self.inaccuracy).min(self.width - 1), (y / self.inaccuracy).min(self.height - 1));
+        &self.tiles[cell_y / LIGHT_TILE_SIZE * tiles_x + cell_x / LIGHT_TILE_SIZE]
+    }
+    /// sorts the lights into the tiles they can reach (using size + range), so cells don't have to check every light.
+    fn bin_lights(&mut self, world: &super::world::World) {
+        let tiles_x = self.width.div_ceil(LIGHT_TILE_SIZE);
+        let tiles_y = self.height.div_ceil(LIGHT_TILE_SIZE);
+        for tile in self.tiles.iter_mut() {
+            tile.clear();
+        }
+        // the inverse of the pixels -> view conversion in calculate
+        let to_cell_x = |view_x: f32| (view_x / world.width + 1.0) * (self.width - 1) as f32 / 2.0;
+        let to_cell_y = |view_y: f32| (view_y / world.height + 1.0) * (self.height - 1) as f32 / 2.0;
+        for (light_index, light_source) in world.lights_rendered.iter().enumerate() {
+            // the reach is a circle, so it has the same size on the screen however the camera is rotated
+            let (view_x, view_y) = world.camera.world_to_view(light_source.x, light_source.y);
+            let light_source_max_reach = (light_source.size + light_source.range) * world.camera.zoom;
+            let left = to_cell_x(view_x - light_source_max_reach).floor();
+            let right = to_cell_x(view_x + light_source_max_reach).ceil();
+            let top = to_cell_y(view_y - light_source_max_reach).floor();
+            let bottom = to_cell_y(view_y + light_source_max_reach).ceil();
+            if right < 0.0 || bottom < 0.0 || left > (self.width - 1) as f32 || top > (self.height - 1) as f32 { continue; } // not on the screen
+            let tile_left = left.max(0.0) as usize / LIGHT_TILE_SIZE;
+            let tile_right = (right as usize).min(self.width - 1) / LIGHT_TILE_SIZE;
+            let tile_top = top.max(0.0) as usize / LIGHT_TILE_SIZE;
+            let tile_bottom = (bottom as usize).min(self.height - 1) / LIGHT_TILE_SIZE;
+            for tile_y in tile_top..=tile_bottom.min(tiles_y - 1) {
+                for tile_x in tile_left..=tile_right.min(tiles_x - 1) {
+                    self.tiles[tile_y * tiles_x + tile_x].push(light_index);
                 }
             }
         }
     }
+    pub fn calculate(&mut self, world: &super::world::World, width: usize, height: usize, parallel: bool, simd: bool) {
+        {
+            // distance between two neighbouring light map cells in world units
+            let cell_size = (2.0 * world.width / (self.width - 1) as f32).min(2.0 * world.height / (self.height - 1) as f32) / world.camera.zoom;
+            let camera = &world.camera;
+            // points on each light which are checked for occluders. bigger lights get more of them, which makes their shadows softer.
+            let shadow_samples: Vec<Vec<(f32, f32)>> = world.lights_rendered.iter().map(|light_source| shadow_samples(light_source.size, cell_size)).collect();
+
+            self.bin_lights(world);
+            let tiles_x = self.width.div_ceil(LIGHT_TILE_SIZE);
+            let (map_width, map_height, tiles) = (self.width, self.height, &self.tiles);
+
+            // one band per row of tiles
+            for_each_band(&mut self.data, map_width, LIGHT_TILE_SIZE, parallel, |first_row, band| {
+                let mut index = 0;
+                for y in first_row..first_row + band.len() / map_width {
+                    let Y = ((y * 2) as f32 / (map_height - 1) as f32 - 1f32) * world.height; // convert the value from pixels to a relative value from -1 to 1
+                    let tiles_row = &tiles[(y / LIGHT_TILE_SIZE) * tiles_x..(y / LIGHT_TILE_SIZE + 1) * tiles_x];
+                    let mut x = 0;
+                    // cells in the same row are only in the same row of the world if the camera isn't rotated
+                    if simd && camera.rotation() == 0.0 {
+                        let world_y = camera.view_to_world(0.0, Y).1;
+                        // 8 cells at once. LIGHT_TILE_SIZE is a multiple of 8, so they are always in the same tile.
+                        while x + 8 <= map_width {
+                            let xs = std::array::from_fn(|i| camera.view_to_world((((x + i) * 2) as f32 / (map_width - 1) as f32 - 1f32) * world.width, Y).0);
+                            band[index..index + 8].copy_from_slice(&super::render_world_simd::light_at_x8(xs, world_y, &tiles_row[x / LIGHT_TILE_SIZE], world, &shadow_samples));
+                            index += 8;
+                            x += 8;
+                        }
+                    }
+                    for x in x..map_width {
+                        let X = ((x * 2) as f32 / (map_width - 1) as f32 - 1f32) * world.width; // convert the value from pixels to a relative value from -1 to 1
+                        let (world_x, world_y) = camera.view_to_world(X, Y);
+                        band[index] = light_at(world_x, world_y, &tiles_row[x / LIGHT_TILE_SIZE], world, &shadow_samples); // only the lights that can reach this cell's tile
+                        index += 1;
+                    }
+                }
+            });
+        }
+    }
     // pub fn calculate_and_join(&mut self, world: &super::world::WorldRenderable, image_data: &mut Vec<u8>, width: u32, height: u32, objects: &ObjectNoLightRenderer) {
     //     {
     //         let image_data_byte_width_without_modified_pixels = 4 * (width - self.inaccuracy) ;
@@ -201,68 +304,454 @@ pub struct LightMap {
     // }
 }
 
+/// how many rows of pixels are drawn at once when rendering in parallel.
+const ROWS_PER_BAND: usize = 16;
+
+/// calls f for each band of rows_per_band rows (each row_length long) of data, with the index of the band's first row.
+/// if parallel is true, the bands are processed on rayon's thread pool, otherwise all rows are passed to f at once.
+/// (on the web, rayon's threads are web workers, which are started by initThreadPool in index.html)
+fn for_each_band<T: Send>(data: &mut [T], row_length: usize, rows_per_band: usize, parallel: bool, f: impl Fn(usize, &mut [T]) + Send + Sync) {
+    if parallel && row_length > 0 {
+        data.par_chunks_mut(row_length * rows_per_band).enumerate().for_each(|(band_index, band)| f(band_index * rows_per_band, band));
+    } else {
+        f(0, data);
+    }
+}
+
+/// like for_each_band, but f gets the same rows of two buffers (which have the same size) at once.
+fn for_each_band_pair<T: Send, U: Send>(data: &mut [T], data2: &mut [U], row_length: usize, rows_per_band: usize, parallel: bool, f: impl Fn(usize, &mut [T], &mut [U]) + Send + Sync) {
+    if parallel && row_length > 0 {
+        data.par_chunks_mut(row_length * rows_per_band).zip(data2.par_chunks_mut(row_length * rows_per_band)).enumerate()
+            .for_each(|(band_index, (band, band2))| f(band_index * rows_per_band, band, band2));
+    } else {
+        f(0, data, data2);
+    }
+}
+
+/// the width and height of the tiles that LightMap sorts its lights into, in light map cells. has to be a multiple of 8 for render_world_simd::light_at_x8.
+const LIGHT_TILE_SIZE: usize = 16;
+
+/// the most light a light map cell can have: overlapping lights add up to at most 64 times full brightness (u16::MAX).
+/// this leaves headroom for render_joiner::ToneMapping and is small enough that the joins can't overflow a u32.
+pub const MAX_LIGHT: u32 = 64 * u16::MAX as u32;
+
+/// adds brightness to light, up to MAX_LIGHT
+pub(crate) fn add_light(light: u32, brightness: u32) -> u32 {
+    (light + brightness).min(MAX_LIGHT)
+}
+
+/// the most points that are checked per light for soft shadows.
+const MAX_SHADOW_SAMPLES: usize = 16;
+
+/// splits the diameter of a light into evenly spaced points, one per light map cell (at least 1, at most MAX_SHADOW_SAMPLES).
+/// returns (offset from the center relative to size (-1 to 1), weight) for each point. the weights add up to 1.
+/// a light with a size of 0 only has a single point at its center, which makes its shadows hard.
+fn shadow_samples(size: f32, cell_size: f32) -> Vec<(f32, f32)> {
+    let count = ((2.0 * size / cell_size).ceil() as usize).clamp(1, MAX_SHADOW_SAMPLES);
+    // seen from the side, a round light is widest at its center, so points near the edge count less.
+    let samples: Vec<(f32, f32)> = (0..count).map(|i| {
+        let offset = (2 * i + 1) as f32 / count as f32 - 1.0;
+        (offset, (1.0 - offset * offset).sqrt())
+    }).collect();
+    let total_weight: f32 = samples.iter().map(|sample| sample.1).sum();
+    samples.into_iter().map(|(offset, weight)| (offset, weight / total_weight)).collect()
+}
+
+/// the light at (world_x, world_y), from the lights (indices into world.lights_rendered) which can reach it.
+pub(crate) fn light_at(world_x: f32, world_y: f32, lights: &[usize], world: &World, shadow_samples: &[Vec<(f32, f32)>]) -> (u32, u32, u32) {
+    let mut r: u32 = 0;
+    let mut g: u32 = 0;
+    let mut b: u32 = 0;
+    for &light_index in lights.iter() {
+        let light_source = &world.lights_rendered[light_index];
+        let shadow_samples = &shadow_samples[light_index];
+        let dist_x = light_source.x - world_x;
+        let dist_y = light_source.y - world_y;
+
+        let light_source_max_reach = light_source.size + light_source.range;
+        let light_source_max_reach_squared = light_source_max_reach * light_source_max_reach;
+
+        if dist_x.abs() > light_source_max_reach || dist_y.abs() > light_source_max_reach { continue; }
+
+        let dist_squared = dist_x * dist_x + dist_y * dist_y; // don't use sqrt because it's rather slow
+
+        if dist_squared >= light_source_max_reach_squared { continue; }
+
+        // spotlights only shine into their cone
+        let cone = light_source.cone.map_or(1.0, |cone| cone.factor(-dist_x, -dist_y));
+        if cone <= 0.0 { continue; }
+
+        // shadows: how much of the light can be seen from this cell (0 = fully blocked, 1 = not blocked at all)
+        let visibility = if world.occluders.is_empty() { 1.0 } else { light_visibility(world, light_source, world_x, world_y, shadow_samples) };
+        if visibility <= 0.0 { continue; }
+
+        let light_source_size_squared = light_source.size * light_source.size;
+
+        if light_source_size_squared >= dist_squared && visibility >= 1.0 && cone >= 1.0 {
+            r = add_light(r, light_source.brightness.0 as u32);
+            g = add_light(g, light_source.brightness.1 as u32);
+            b = add_light(b, light_source.brightness.2 as u32);
+        } else {
+            let factor = if light_source_size_squared >= dist_squared { 1.0 } else { light_source.falloff.factor(dist_squared, light_source.size, light_source_max_reach) };
+            let factor = factor * visibility * cone;
+            let factor_int = (factor * u16::MAX as f32) as u32;
+            r = add_light(r, (light_source.brightness.0 as u32 * factor_int) >> 16);
+            g = add_light(g, (light_source.brightness.1 as u32 * factor_int) >> 16);
+            b = add_light(b, (light_source.brightness.2 as u32 * factor_int) >> 16);
+        };
+    };
+    with_global_lights((r, g, b), world_x, world_y, world)
+}
+
+/// light plus world.ambient and the light of world.directional_lights at (world_x, world_y), unless an occluder casts a shadow onto that point.
+pub(crate) fn with_global_lights(light: (u32, u32, u32), world_x: f32, world_y: f32, world: &World) -> (u32, u32, u32) {
+    let mut r = add_light(light.0, world.ambient.0 as u32);
+    let mut g = add_light(light.1, world.ambient.1 as u32);
+    let mut b = add_light(light.2, world.ambient.2 as u32);
+    for directional_light in world.directional_lights.iter() {
+        if directional_light.shadow_length > 0.0 {
+            // the light comes from the opposite direction, so look that way for something in between
+            let (sin, cos) = directional_light.direction.sin_cos();
+            let (from_x, from_y) = (world_x - cos * directional_light.shadow_length, world_y - sin * directional_light.shadow_length);
+            if world.occluders.iter().any(|occluder| occluder.blocks(world_x, world_y, from_x, from_y)) { continue; }
+        }
+        r = add_light(r, directional_light.brightness.0 as u32);
+        g = add_light(g, directional_light.brightness.1 as u32);
+        b = add_light(b, directional_light.brightness.2 as u32);
+    }
+    (r, g, b)
+}
+
+/// which part of light_source can be seen from (x, y), from 0 to 1.
+/// the light's shadow_samples are spread out perpendicular to the direction from (x, y) to the light.
+pub(crate) fn light_visibility(world: &World, light_source: &super::world::Object::Objects::LightObject, x: f32, y: f32, shadow_samples: &[(f32, f32)]) -> f32 {
+    let dist_x = light_source.x - x;
+    let dist_y = light_source.y - y;
+    let dist = (dist_x * dist_x + dist_y * dist_y).sqrt();
+    let (perpendicular_x, perpendicular_y) = if dist > 0.0 { (-dist_y / dist * light_source.size, dist_x / dist * light_source.size) } else { (light_source.size, 0.0) };
+    let mut visibility = 0.0;
+    let mut blocked_any = false;
+    for (offset, weight) in shadow_samples {
+        let sample_x = light_source.x + perpendicular_x * offset;
+        let sample_y = light_source.y + perpendicular_y * offset;
+        if world.occluders.iter().any(|occluder| occluder.blocks(x, y, sample_x, sample_y)) {
+            blocked_any = true;
+        } else {
+            visibility += weight;
+        }
+    }
+    // the weights might not add up to exactly 1, but a light that isn't blocked at all should look exactly like it did without shadows.
+    if blocked_any { visibility } else { 1.0 }
+}
+
 
 pub struct ObjectNoLightRenderer {
     width: usize,
     height: usize,
-    buffer: Vec<(u8, u8, u8)>
+    buffer: Vec<Pixel>,
+    /// the parts of the pixels that aren't lit, added to buffer after it has been lit (see Layer::draw_onto_split_rows).
+    /// empty if everything is lit.
+    unlit: Vec<Pixel>,
 } impl ObjectNoLightRenderer {
     pub fn new(w: usize, h: usize) -> Self {
         Self {
-            width: w, height: h, buffer: vec![(0,0,0); w*h],
+            width: w, height: h, buffer: vec![Pixel::TRANSPARENT; w*h], unlit: vec![],
         }
     }
+    /// row y of unlit, or nothing if everything is lit
+    fn unlit_row(&self, y: usize) -> &[Pixel] {
+        if self.unlit.is_empty() { &[] } else { &self.unlit[y * self.width..(y + 1) * self.width] }
+    }
     pub fn draw_init(&mut self, world: &mut crate::world::world::World) {
         // call draw_init on the objects
         for object in world.objects_rendered.iter_mut() {
+            object.state.place_in_world(&world.camera, (world.width, world.height));
             (object.fns.draw_init)(&mut object.state);
         }
+        // shared objects get the pixels of their object once it has drawn them
+        share_pixels(&mut world.objects_rendered);
     }
-    pub fn draw_all(&mut self, world: &mut World) {
+    pub fn draw_all(&mut self, world: &mut World, parallel: bool) {
         self.draw_to_layers(world);
-        self.draw_to_buffer(world);
+        self.draw_to_buffer(world, parallel);
     }
     pub fn draw_to_layers(&mut self, world: &mut World) {
         let elapsed_time = world.start_time.elapsed();
+        // shared objects let go of the pixels while the objects draw, so drawing doesn't have to copy them (see Layer::pixels_mut)
         for object in world.objects_rendered.iter_mut() {
+            if let WorldObjectData::Shared { .. } = object.state.data {
+                object.state.layer.pixel_data = Default::default();
+            }
+        }
+        for object in world.objects_rendered.iter_mut() {
+            object.state.place_in_world(&world.camera, (world.width, world.height));
             (object.fns.draw_again)(&mut object.state, &elapsed_time);
         }
+        // and get whatever their objects have drawn this frame
+        share_pixels(&mut world.objects_rendered);
+    }
+    pub fn draw_to_buffer(&mut self, world: &crate::world::world::World, parallel: bool) {
+        let width = self.width;
+        debug_assert_eq!(self.buffer.len(), width * self.height);
+        let height = self.height;
+        // the background only has to be drawn where no opaque layer will be drawn over it
+        let opaque_areas: Vec<_> = world.objects_rendered.iter().filter_map(|object| object.state.layer.opaque_area(width, height)).collect();
+        // by render layer, then z_index. sort_by_key is stable, so objects that were added later stay on top.
+        let mut order: Vec<&WorldObject> = world.objects_rendered.iter().collect();
+        order.sort_by_key(|object| (object.state.render_layer, object.state.z_index));
+        let background_lit = world.lighting(RenderLayer::Background);
+        let draw_band = |first_row: usize, band: &mut [Pixel], mut unlit_band: Option<&mut [Pixel]>| {
+            let mut covered = vec![];
+            for y in 0..band.len() / width {
+                let line = &mut band[y * width..(y + 1) * width];
+                let mut unlit_line = unlit_band.as_deref_mut().map(|unlit_band| &mut unlit_band[y * width..(y + 1) * width]);
+                let y = first_row + y;
+                let mut draw_background = |x0: usize, x1: usize| match unlit_line.as_deref_mut() {
+                    None => world.background.draw_onto_line(line, x0, x1, y, height),
+                    Some(unlit_line) if background_lit => {
+                        world.background.draw_onto_line(line, x0, x1, y, height);
+                        unlit_line[x0..x1].fill(Pixel::TRANSPARENT);
+                    },
+                    Some(unlit_line) => {
+                        world.background.draw_onto_line(unlit_line, x0, x1, y, height);
+                        line[x0..x1].fill(Pixel::opaque(0, 0, 0));
+                    },
+                };
+                covered.clear();
+                covered.extend(opaque_areas.iter().filter(|area| area.2 <= y && y < area.3).map(|area| (area.0, area.1)));
+                covered.sort_unstable();
+                let mut x = 0;
+                for &(x0, x1) in covered.iter() {
+                    if x0 > x { draw_background(x, x0); }
+                    x = x.max(x1);
+                }
+                if x < width { draw_background(x, width); }
+            }
+            for object in order.iter() {
+                match unlit_band.as_deref_mut() {
+                    None => object.state.layer.draw_onto_rows(band, first_row, width),
+                    Some(unlit_band) => object.state.layer.draw_onto_split_rows(band, unlit_band, world.lighting(object.state.render_layer), first_row, width),
+                }
+            }
+        };
+        // the unlit buffer is only needed if something isn't lit or glows
+        if background_lit && world.objects_rendered.iter().all(|object| world.lighting(object.state.render_layer) && object.state.layer.emission == Emission::None) {
+            self.unlit = vec![];
+            for_each_band(&mut self.buffer, width, ROWS_PER_BAND, parallel, |first_row, band| draw_band(first_row, band, None));
+        } else {
+            self.unlit.resize(width * height, Pixel::TRANSPARENT);
+            for_each_band_pair(&mut self.buffer, &mut self.unlit, width, ROWS_PER_BAND, parallel, |first_row, band, unlit_band| draw_band(first_row, band, Some(unlit_band)));
+        }
     }
-    pub fn draw_to_buffer(&mut self, world: &crate::world::world::World) {
-        for object in world.objects_rendered.iter() {
-            object.state.layer.draw_onto(&mut self.buffer, self.width, self.height);
+}
+
+/// gives the shared objects the pixels of the objects they show, see WorldObjectData::Shared
+fn share_pixels(objects: &mut [WorldObject]) {
+    for index in 0..objects.len() {
+        let WorldObjectData::Shared { object } = objects[index].state.data else { continue };
+        // follows shared objects that show shared objects, but not in circles
+        let mut source = object;
+        for _ in 0..objects.len() {
+            let Some(WorldObjectData::Shared { object }) = objects.get(source).map(|source| &source.state.data) else { break };
+            source = *object;
+        }
+        if !objects.get(source).is_some_and(|source| !matches!(source.state.data, WorldObjectData::Shared { .. })) {
+            let layer = &mut objects[index].state.layer;
+            (layer.pos_w, layer.pos_h, layer.pixel_data) = (0, 0, Default::default());
+            continue;
         }
+        let (shared, source) = if index < source {
+            let (before, after) = objects.split_at_mut(source);
+            (&mut before[index], &after[0])
+        } else {
+            let (before, after) = objects.split_at_mut(index);
+            (&mut after[0], &before[source])
+        };
+        shared.state.layer.share_pixels_of(&source.state.layer);
     }
 }
 
 
 pub mod render_joiner {
 
-    use super::{ObjectNoLightRenderer, LightMap, WorldRenderer};
+    use super::{ObjectNoLightRenderer, LightMap, WorldRenderer, Pixel, for_each_band, ROWS_PER_BAND, MAX_LIGHT};
+
+    /// which light map cells are used for a pixel on the screen
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum JoinMode {
+        /// use the cell the pixel is in (join5). fastest, but light edges look blocky if inaccuracy is 2 or more.
+        Nearest,
+        /// blend between the four cells closest to the pixel (join6).
+        Bilinear,
+    }
+
+    /// how a lit colour value is turned into a byte for the screen. lit values go up to 64 times what a white object in full brightness gets
+    /// (see MAX_LIGHT), which is more than the screen can show.
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum ToneMapping {
+        /// everything brighter than full brightness is white. overlapping lights look flat, because they are cut off at the same value.
+        Clamp,
+        /// x / (1 + x): never quite white, so brighter light still looks brighter. full brightness is shown at half brightness.
+        Reinhard,
+        /// a filmic curve close to the one of ACES, with more contrast than Reinhard. full brightness is shown at about 80%.
+        Aces,
+    }
+
+    impl ToneMapping {
+        /// maps a lit colour value (1 is a white object in full brightness) to what is shown on the screen (0 to 1)
+        pub fn map(self, x: f32) -> f32 {
+            match self {
+                Self::Clamp => x.min(1.0),
+                Self::Reinhard => x / (1.0 + x),
+                Self::Aces => (x * (2.51 * x + 0.03) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0),
+            }
+        }
+    }
+
+    /// the most a colour value can be in linear colour space, see ToneCurve
+    const LINEAR_MAX: u16 = 4095;
+
+    fn srgb_to_linear(value: f32) -> f32 {
+        if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+    }
+
+    fn linear_to_srgb(value: f32) -> f32 {
+        if value <= 0.0031308 { value * 12.92 } else { 1.055 * value.powf(1.0 / 2.4) - 0.055 }
+    }
+
+    /// lookup tables that light a colour value (see lit), so join_row doesn't have to do any floating point maths.
+    ///
+    /// without linear lighting, colour values are multiplied by the light like they are (see multiply_factor) and mapped through the ToneMapping.
+    /// with linear lighting, colour values are converted to linear colour space first (0 to LINEAR_MAX, 12 bits are enough for the darkest srgb values),
+    /// multiplied by the light there and converted back to srgb together with the tone mapping.
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct ToneCurve {
+        tone_mapping: ToneMapping,
+        linear: bool,
+        /// the value that is multiplied by the light, for each srgb value
+        pub(crate) to_lit: [u16; 256],
+        /// the light is shifted right by this much before it is multiplied, so 12 bit linear values times MAX_LIGHT fit into a u32
+        pub(crate) light_shift: u32,
+        /// the srgb value shown for each lit value
+        pub(crate) curve: Vec<u8>,
+    }
+
+    impl ToneCurve {
+        pub fn new(tone_mapping: ToneMapping, linear: bool) -> Self {
+            let (to_lit, light_shift, one) = if linear {
+                (std::array::from_fn(|value| (srgb_to_linear(value as f32 / 255.0) * LINEAR_MAX as f32).round() as u16), 4, LINEAR_MAX as f32)
+            } else {
+                (std::array::from_fn(|value| value as u16), 0, 255.0)
+            };
+            let brightest = (to_lit[255] as u32 * (MAX_LIGHT >> light_shift)) >> (16 - light_shift);
+            let curve = (0..=brightest).map(|lit| {
+                let shown = tone_mapping.map(lit as f32 / one);
+                let shown = if linear { linear_to_srgb(shown) } else { shown };
+                (shown * 255.0).round() as u8
+            }).collect();
+            Self { tone_mapping, linear, to_lit, light_shift, curve }
+        }
+        /// if this was made for tone_mapping and linear
+        pub fn is_for(&self, tone_mapping: ToneMapping, linear: bool) -> bool {
+            (self.tone_mapping, self.linear) == (tone_mapping, linear)
+        }
+        /// the srgb value shown for the srgb value value lit by light. without linear lighting, this is multiply_factor mapped through the ToneMapping.
+        pub fn lit(&self, value: u8, light: u32) -> u8 {
+            self.curve[((self.to_lit[value as usize] as u32 * (light >> self.light_shift)) >> (16 - self.light_shift)) as usize]
+        }
+    }
 
     pub fn join(buffer: &mut Vec<u8>, data: &WorldRenderer) {
+        let tone_curve = &data.tone_curve;
         // there are multiple functions which can join light and object data
-        join5(buffer, data)
+        match data.join_mode {
+            JoinMode::Nearest => join5(buffer, data, tone_curve),
+            JoinMode::Bilinear => join6(buffer, data, tone_curve),
+        }
     }
 
-    fn join5(buffer: &mut Vec<u8>, data: &WorldRenderer) {
-        let mut objects_index = 0;
-        let mut buffer_index = 0;
-        for y_buffer in 0..data.height { // for each line of pixels on the screen
+    fn join5(buffer: &mut [u8], data: &WorldRenderer, tone_curve: &ToneCurve) {
+        // the light map column for each pixel in a line
+        let columns: Vec<usize> = (0..data.width).map(|x_buffer| x_buffer * data.lights_renderer.width / data.width).collect();
+        for_each_band(buffer, data.width * 4, ROWS_PER_BAND, data.parallel, |first_row, band| join5_rows(band, first_row, data, &columns, tone_curve));
+    }
+
+    fn join5_rows(buffer: &mut [u8], first_row: usize, data: &WorldRenderer, columns: &[usize], tone_curve: &ToneCurve) {
+        let mut lights_row = vec![(0, 0, 0); data.width];
+        let mut lights_index_row_prev = None;
+        for (y_buffer, buffer_row) in (first_row..).zip(buffer.chunks_mut(data.width * 4)) { // for each line of pixels on the screen
             let lights_index_row = (y_buffer * data.lights_renderer.height / data.height) * data.lights_renderer.width;
-            for x_buffer in 0..data.width { // for each pixel in this line
-                let lights_index_pixel = lights_index_row + x_buffer * data.lights_renderer.width / data.width;
-                let light = data.lights_renderer.data[lights_index_pixel];
-                let obj = &data.objects_renderer.buffer[objects_index];
-                buffer[buffer_index] = multiply_factor(obj.0, light.0);
+            if lights_index_row_prev != Some(lights_index_row) { // neighbouring lines often use the same light map row
+                for (light, column) in lights_row.iter_mut().zip(columns.iter()) { // for each pixel in this line
+                    *light = data.lights_renderer.data[lights_index_row + column];
+                }
+                lights_index_row_prev = Some(lights_index_row);
+            }
+            join_row(buffer_row, &data.objects_renderer.buffer[y_buffer * data.width..(y_buffer + 1) * data.width], data.objects_renderer.unlit_row(y_buffer), &lights_row, tone_curve, data.simd);
+        }
+    }
+
+    /// for each pixel (0..pixels) in one direction, the two closest light map cells (of cells) and how much of the second one should be used (0 to 256).
+    /// this matches LightMap::calculate, where the first cell is at the first pixel and the last cell is at the last pixel.
+    fn bilinear_steps(pixels: usize, cells: usize) -> Vec<(usize, usize, u32)> {
+        (0..pixels).map(|pixel| {
+            let pos = if pixels > 1 { pixel * (cells - 1) * 256 / (pixels - 1) } else { 0 }; // position in cells, multiplied by 256
+            let first = pos / 256;
+            (first, (first + 1).min(cells - 1), (pos % 256) as u32)
+        }).collect()
+    }
+
+    fn join6(buffer: &mut [u8], data: &WorldRenderer, tone_curve: &ToneCurve) {
+        let columns = bilinear_steps(data.width, data.lights_renderer.width);
+        let rows = bilinear_steps(data.height, data.lights_renderer.height);
+        for_each_band(buffer, data.width * 4, ROWS_PER_BAND, data.parallel, |first_row, band| join6_rows(band, first_row, data, &columns, &rows, tone_curve));
+    }
+
+    fn join6_rows(buffer: &mut [u8], first_row: usize, data: &WorldRenderer, columns: &[(usize, usize, u32)], rows: &[(usize, usize, u32)], tone_curve: &ToneCurve) {
+        let lights = &data.lights_renderer;
+        let mut lights_row = vec![(0, 0, 0); data.width];
+        for (y_buffer, buffer_row) in (first_row..).zip(buffer.chunks_mut(data.width * 4)) { // for each line of pixels on the screen
+            let (row_top, row_bottom, weight_bottom) = rows[y_buffer];
+            let weight_top = 256 - weight_bottom;
+            let top = &lights.data[row_top * lights.width..(row_top + 1) * lights.width];
+            let bottom = &lights.data[row_bottom * lights.width..(row_bottom + 1) * lights.width];
+            for (&(column_left, column_right, weight_right), light) in columns.iter().zip(lights_row.iter_mut()) { // for each pixel in this line
+                let weight_left = 256 - weight_right;
+                // MAX_LIGHT * 256 * 2 still fits into a u32
+                let blend = |top_left: u32, top_right: u32, bottom_left: u32, bottom_right: u32| {
+                    let top = (top_left * weight_left + top_right * weight_right) >> 8;
+                    let bottom = (bottom_left * weight_left + bottom_right * weight_right) >> 8;
+                    (top * weight_top + bottom * weight_bottom) >> 8
+                };
+                let (top_left, top_right, bottom_left, bottom_right) = (top[column_left], top[column_right], bottom[column_left], bottom[column_right]);
+                *light = (blend(top_left.0, top_right.0, bottom_left.0, bottom_right.0), blend(top_left.1, top_right.1, bottom_left.1, bottom_right.1), blend(top_left.2, top_right.2, bottom_left.2, bottom_right.2));
+            }
+            join_row(buffer_row, &data.objects_renderer.buffer[y_buffer * data.width..(y_buffer + 1) * data.width], data.objects_renderer.unlit_row(y_buffer), &lights_row, tone_curve, data.simd);
+        }
+    }
+
+    /// lights one row of objects with one light per pixel (see ToneCurve::lit),
+    /// adds the unlit parts (if there are any) and writes the rgb values to buffer (4 bytes per pixel, alpha is not changed).
+    fn join_row(buffer: &mut [u8], objects: &[Pixel], unlit: &[Pixel], lights: &[(u32, u32, u32)], tone_curve: &ToneCurve, simd: bool) {
+        if simd {
+            super::super::render_world_simd::join_row(buffer, objects, lights, tone_curve);
+        } else {
+            let mut buffer_index = 0;
+            for (obj, light) in objects.iter().zip(lights.iter()) {
+                buffer[buffer_index] = tone_curve.lit(obj.r, light.0);
                 buffer_index += 1;
-                buffer[buffer_index] = multiply_factor(obj.1, light.1);
+                buffer[buffer_index] = tone_curve.lit(obj.g, light.1);
                 buffer_index += 1;
-                buffer[buffer_index] = multiply_factor(obj.2, light.2);
+                buffer[buffer_index] = tone_curve.lit(obj.b, light.2);
                 buffer_index += 2;
-                objects_index += 1;
             }
         }
+        // the unlit parts of the pixels are added after lighting
+        for (buffer, unlit) in buffer.chunks_exact_mut(4).zip(unlit.iter()) {
+            buffer[0] = buffer[0].saturating_add(unlit.r);
+            buffer[1] = buffer[1].saturating_add(unlit.g);
+            buffer[2] = buffer[2].saturating_add(unlit.b);
+        }
     }
 
     // ALL OF THESE WERE MADE BEFORE OBJECTS WERE DRAWN WITH LAYERS!
@@ -403,7 +892,8 @@ pub mod render_joiner {
     //     }
     // }
 
-    pub fn multiply_factor(n1: u8, n2: u16) -> u8 {
-        ((n1 as usize * n2 as usize) >> 16) as u8
+    /// a colour value n1 lit by the light n2. up to 255 with a light of full brightness (u16::MAX), more with brighter light.
+    pub fn multiply_factor(n1: u8, n2: u32) -> u32 {
+        (n1 as u32 * n2) >> 16
     }
 }
\ No newline at end of file
@@ -1,12 +1,15 @@
+use std::sync::Arc;
+
 pub struct Layer {
     /// the original x-position of this layer on the screen in pixels
-    pub pos_x_start: usize,
+    pub pos_x_start: f32,
     /// the original y-position of this layer on the screen in pixels
-    pub pos_y_start: usize,
-    /// the current x-position of this layer on the screen in pixels
-    pub pos_x: usize,
-    /// the current y-position of this layer on the screen in pixels
-    pub pos_y: usize,
+    pub pos_y_start: f32,
+    /// the current x-position of this layer on the screen in pixels. can be negative or between two pixels,
+    /// the layer is drawn at the nearest pixel and everything outside of the screen is cut off.
+    pub pos_x: f32,
+    /// the current y-position of this layer on the screen in pixels, like pos_x
+    pub pos_y: f32,
     /// the width of this layer on the screen in pixels
     pub pos_w: usize,
     /// the height of this layer on the screen in pixels
@@ -15,10 +18,22 @@ pub struct Layer {
     pub width: usize,
     /// the height of the entire screen in pixels
     pub height: usize,
-    pub pixel_data: Vec<Vec<Pixel>>,
+    /// pos_w * pos_h pixels, line by line. can be shared with other layers that look the same (see share_pixels_of).
+    pub pixel_data: Arc<Vec<Pixel>>,
+    /// how the pixels are drawn onto what is below this layer
+    pub blend_mode: BlendMode,
+    /// true if every pixel has an alpha of 255, so (with BlendMode::Normal) nothing behind this layer has to be drawn.
+    /// pixels_mut resets it, whatever draws into the layer can set it again.
+    pub opaque: bool,
+    /// how much bigger the layer is drawn on the screen, around its top left corner (pos_x, pos_y). set by the camera for objects in the world.
+    pub zoom: f32,
+    /// how much the layer is turned (clockwise, in radians) around its top left corner when it is drawn, like zoom
+    pub rotation: f32,
+    /// the light the layer gives off itself, which is added after lighting so it glows even in the dark. only draw_onto_split_rows draws it.
+    pub emission: Emission,
 }
 impl Layer {
-    pub fn new(x: usize, y: usize, w: usize, h: usize, width: usize, height: usize) -> Self {
+    pub fn new(x: f32, y: f32, w: usize, h: usize, width: usize, height: usize) -> Self {
         Self {
             pos_x_start: x,
             pos_y_start: y,
@@ -28,59 +43,397 @@ impl Layer {
             pos_h: h,
             width: width,
             height: height,
-            pixel_data: { // generate 2d vec of transparent pixels //
-                let mut v = vec![];
-                for _ in 0..height {
-                    let mut v2 = vec![];
-                    for _ in 0..width {
-                        v2.push(Pixel::Transparent);
-                    }
-                    v.push(v2);
+            pixel_data: Arc::new(vec![Pixel::TRANSPARENT; w * h]),
+            blend_mode: BlendMode::Normal,
+            opaque: false,
+            zoom: 1.0,
+            rotation: 0.0,
+            emission: Emission::None,
+        }
+    }
+    /// uses the same pixels as other (with their size) instead of its own. they are only copied once one of the layers is changed (see pixels_mut).
+    /// everything else, like the position, blend mode and emission, stays like it is.
+    pub fn share_pixels_of(&mut self, other: &Layer) {
+        self.pos_w = other.pos_w;
+        self.pos_h = other.pos_h;
+        self.pixel_data = Arc::clone(&other.pixel_data);
+        self.opaque = other.opaque;
+    }
+    /// moves the layer to (x, y) and changes its size to w x h. the pixels are kept if the size stays the same, otherwise they are all transparent.
+    pub fn resize(&mut self, x: f32, y: f32, w: usize, h: usize) {
+        self.pos_x = x;
+        self.pos_y = y;
+        if (w, h) != (self.pos_w, self.pos_h) {
+            self.pos_w = w;
+            self.pos_h = h;
+            self.pixel_data = Arc::new(vec![Pixel::TRANSPARENT; w * h]);
+            self.opaque = false;
+        }
+    }
+    /// the pixels in line y of this layer.
+    pub fn line(&self, y: usize) -> &[Pixel] {
+        &self.pixel_data[y * self.pos_w..(y + 1) * self.pos_w]
+    }
+    /// all pixels of this layer, line by line. if they are shared with other layers, this layer gets its own copy first.
+    pub fn pixels_mut(&mut self) -> &mut [Pixel] {
+        self.opaque = false;
+        Arc::make_mut(&mut self.pixel_data).as_mut_slice()
+    }
+    /// scales the part of an image at (x, y) with size (w, h) to the size of this layer and draws it into the layer with transform, replacing everything.
+    /// rgba is not premultiplied (like in png files) and image_width pixels wide.
+    pub fn draw_image(&mut self, rgba: &[u8], image_width: usize, (x, y, w, h): (usize, usize, usize, usize), transform: &ImageTransform) {
+        if *transform != ImageTransform::default() {
+            return self.draw_image_transformed(rgba, image_width, (x, y, w, h), transform);
+        }
+        let (pos_w, pos_h) = (self.pos_w, self.pos_h);
+        let mut opaque = true;
+        // the image pixel under the centre of each layer pixel, like ImageFilter::Nearest in draw_image_transformed
+        for (line_y, line) in self.pixels_mut().chunks_mut(pos_w.max(1)).enumerate() {
+            let img_index_line = (y + (2 * line_y + 1) * h / (2 * pos_h)) * image_width + x;
+            for (line_x, pixel) in line.iter_mut().enumerate() {
+                let img_index = (img_index_line + (2 * line_x + 1) * w / (2 * pos_w)) * 4;
+                *pixel = Pixel::from_straight_alpha(rgba[img_index], rgba[img_index+1], rgba[img_index+2], rgba[img_index+3]);
+                opaque &= pixel.a == 255;
+            }
+        }
+        self.opaque = opaque;
+    }
+    fn draw_image_transformed(&mut self, rgba: &[u8], image_width: usize, (x, y, w, h): (usize, usize, usize, usize), transform: &ImageTransform) {
+        // premultiplied, so the filters don't mix in the colour of transparent pixels
+        let image: Vec<Pixel> = (y..y + h).flat_map(|image_y| (x..x + w).map(move |image_x| {
+            let img_index = (image_y * image_width + image_x) * 4;
+            Pixel::from_straight_alpha(rgba[img_index], rgba[img_index+1], rgba[img_index+2], rgba[img_index+3])
+        })).collect();
+        let (pos_w, pos_h) = (self.pos_w, self.pos_h);
+        let (w_f, h_f) = (w as f32, h as f32);
+        // the size of the image in layer pixels, and image pixels per layer pixel
+        let (span_x, span_y) = (pos_w as f32 * transform.scale.0, pos_h as f32 * transform.scale.1);
+        let (step_x, step_y) = (w_f / span_x, h_f / span_y);
+        let visible = transform.scale.0 > 0.0 && transform.scale.1 > 0.0 && w > 0 && h > 0;
+        // layer pixels are rotated back to find where they are in the image
+        let (sin, cos) = (-transform.rotation).sin_cos();
+        let mut opaque = true;
+        for (line_y, line) in self.pixels_mut().chunks_mut(pos_w.max(1)).enumerate() {
+            for (line_x, pixel) in line.iter_mut().enumerate() {
+                let dx = line_x as f32 + 0.5 - pos_w as f32 / 2.0;
+                let dy = line_y as f32 + 0.5 - pos_h as f32 / 2.0;
+                // multiplied before dividing, so pixel centres that end up on an image pixel's edge land exactly on it
+                let mut image_x = (dx * cos - dy * sin) * w_f / span_x + w_f / 2.0;
+                let mut image_y = (dx * sin + dy * cos) * h_f / span_y + h_f / 2.0;
+                if transform.flip_h { image_x = w_f - image_x; }
+                if transform.flip_v { image_y = h_f - image_y; }
+                *pixel = if !visible || !(0.0..w_f).contains(&image_x) || !(0.0..h_f).contains(&image_y) {
+                    Pixel::TRANSPARENT
+                } else {
+                    transform.filter.sample(&image, (w, h), (image_x, image_y), (step_x, step_y)).tinted(transform.tint)
+                };
+                opaque &= pixel.a == 255;
+            }
+        }
+        self.opaque = opaque;
+    }
+    /// the part of a width x height screen this layer covers completely: (first column, last column + 1, first row, last row + 1).
+    /// None if the layer isn't opaque, doesn't use BlendMode::Normal, is zoomed or rotated or is entirely off screen.
+    pub fn opaque_area(&self, width: usize, height: usize) -> Option<(usize, usize, usize, usize)> {
+        if !self.opaque || self.blend_mode != BlendMode::Normal || self.is_transformed() { return None; }
+        let (pos_x, pos_y) = (self.pos_x.round() as isize, self.pos_y.round() as isize);
+        let x0 = pos_x.clamp(0, width as isize) as usize;
+        let x1 = (pos_x + self.pos_w as isize).clamp(0, width as isize) as usize;
+        let y0 = pos_y.clamp(0, height as isize) as usize;
+        let y1 = (pos_y + self.pos_h as isize).clamp(0, height as isize) as usize;
+        if x0 == x1 || y0 == y1 { None } else { Some((x0, x1, y0, y1)) }
+    }
+    pub fn draw_onto(&self, image_bytes: &mut Vec<Pixel>, width: usize, height: usize) {
+        self.draw_onto_rows(image_bytes, 0, width);
+    }
+    /// like draw_onto, but rows only contains some rows of the screen, starting at first_row.
+    pub fn draw_onto_rows(&self, rows: &mut [Pixel], first_row: usize, width: usize) {
+        if self.is_transformed() {
+            return self.for_each_transformed_pixel(rows.len(), first_row, width, |pixel, index, _| {
+                if pixel.a != 0 {
+                    rows[index] = pixel.blend_onto(rows[index], self.blend_mode);
                 }
-                v
-            },
+            });
         }
+        self.for_each_visible_line(rows.len(), first_row, width, |pixels, index, _| {
+            for (pixel, below) in pixels.iter().zip(rows[index..].iter_mut()) {
+                // fully transparent pixels don't change anything, whatever the blend mode
+                if pixel.a != 0 {
+                    *below = pixel.blend_onto(*below, self.blend_mode);
+                }
+            }
+        });
     }
-    pub fn draw_onto(&self, image_bytes: &mut Vec<(u8, u8, u8)>, width: usize, height: usize) {
-        let width_left = self.pos_x;
-        let width_line = width;
-        let mut line_start_index = self.pos_y * width_line;
-        for line in 0..self.pos_h {
-            let mut index = line_start_index + width_left;
-            for pixel in self.pixel_data[line].iter() {
-                match *pixel {
-                    Pixel::Transparent => {
-                    },
-                    Pixel::Opaque { r, g, b } => {
-                        image_bytes[index] = (r, g, b);
-                    },
-                    Pixel::SemiTransparent { r, g, b, a } => {
-                        let na = 1.0 - a;
-                        let old = image_bytes[index];
-                        image_bytes[index] = ((na * old.0 as f32 + a * r) as u8, (na * old.1 as f32 + a * g) as u8, (na * old.2 as f32 + a * b) as u8);
-                    },
+    /// like draw_onto_rows, for a screen that is split up into pixels that are lit (rows) and pixels that are not (unlit_rows),
+    /// which are added together after rows has been lit. lit says which part this layer belongs to.
+    /// rows always has the alpha of everything together, the alpha of unlit_rows doesn't mean anything.
+    /// the emission of the layer always goes into unlit_rows, on top of the layer itself.
+    pub fn draw_onto_split_rows(&self, rows: &mut [Pixel], unlit_rows: &mut [Pixel], lit: bool, first_row: usize, width: usize) {
+        let blend = |pixel: &Pixel, (layer_x, layer_y): (usize, usize), below: &mut Pixel, below_unlit: &mut Pixel| {
+            if pixel.a != 0 {
+                let (own, keep) = pixel.blend_parts(below.a, self.blend_mode);
+                let emission = self.emission_at(*pixel, layer_x, layer_y);
+                if lit {
+                    *below = own.plus_scaled(*below, keep);
+                    *below_unlit = emission.plus_scaled(*below_unlit, keep);
+                } else {
+                    *below_unlit = own.plus_scaled(*below_unlit, keep).plus_scaled(emission, Pixel::opaque(255, 255, 255));
+                    *below = Pixel { a: own.a, ..Pixel::TRANSPARENT }.plus_scaled(*below, keep);
                 }
-                index += 1;
             }
+        };
+        if self.is_transformed() {
+            return self.for_each_transformed_pixel(rows.len(), first_row, width, |pixel, index, layer_position| blend(&pixel, layer_position, &mut rows[index], &mut unlit_rows[index]));
+        }
+        self.for_each_visible_line(rows.len(), first_row, width, |pixels, index, (first_column, line)| {
+            for (column, ((pixel, below), below_unlit)) in (first_column..).zip(pixels.iter().zip(rows[index..].iter_mut()).zip(unlit_rows[index..].iter_mut())) {
+                blend(pixel, (column, line), below, below_unlit);
+            }
+        });
+    }
+    /// what pixel (at (x, y) in this layer) adds to the unlit part of the screen because of emission
+    fn emission_at(&self, pixel: Pixel, x: usize, y: usize) -> Pixel {
+        match &self.emission {
+            Emission::None => Pixel::TRANSPARENT,
+            Emission::Color(color) => pixel.tinted(*color),
+            Emission::Mask { pixels, width, height } => {
+                if *width == 0 || *height == 0 { return Pixel::TRANSPARENT; }
+                // stretched over the layer, and only where the layer is (partly transparent edges glow less)
+                let mask = pixels[(y * height / self.pos_h) * width + x * width / self.pos_w];
+                mask.tinted(Pixel { r: pixel.a, g: pixel.a, b: pixel.a, a: pixel.a })
+            },
+        }
+    }
+    /// true if the layer is zoomed or rotated, so it can't be drawn line by line
+    fn is_transformed(&self) -> bool {
+        self.zoom != 1.0 || self.rotation != 0.0
+    }
+    /// like for_each_visible_line for a zoomed or rotated layer: calls f with the pixel of this layer that is closest to the centre of each
+    /// screen pixel in rows (after turning and scaling it back), the index in rows and where the pixel is in the layer, for all screen pixels the layer covers.
+    fn for_each_transformed_pixel(&self, rows: usize, first_row: usize, width: usize, mut f: impl FnMut(Pixel, usize, (usize, usize))) {
+        if self.zoom <= 0.0 || self.pos_w == 0 || self.pos_h == 0 || width == 0 { return; }
+        let (sin, cos) = self.rotation.sin_cos();
+        let (layer_w, layer_h) = (self.pos_w as f32 * self.zoom, self.pos_h as f32 * self.zoom);
+        // the corners of the layer on the screen, to only look at the pixels inside of them
+        let corners = [(0.0, 0.0), (layer_w, 0.0), (0.0, layer_h), (layer_w, layer_h)].map(|(x, y)| (self.pos_x + x * cos - y * sin, self.pos_y + x * sin + y * cos));
+        let (min_x, max_x) = corners.iter().fold((f32::MAX, f32::MIN), |(min, max), corner| (min.min(corner.0), max.max(corner.0)));
+        let (min_y, max_y) = corners.iter().fold((f32::MAX, f32::MIN), |(min, max), corner| (min.min(corner.1), max.max(corner.1)));
+        let row_count = rows / width;
+        let x0 = min_x.floor().clamp(0.0, width as f32) as usize;
+        let x1 = max_x.ceil().clamp(0.0, width as f32) as usize;
+        let y0 = (min_y.floor() - first_row as f32).clamp(0.0, row_count as f32) as usize;
+        let y1 = (max_y.ceil() - first_row as f32).clamp(0.0, row_count as f32) as usize;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                // the centre of the screen pixel, turned back and unscaled into the layer
+                let dx = x as f32 + 0.5 - self.pos_x;
+                let dy = (first_row + y) as f32 + 0.5 - self.pos_y;
+                let layer_x = (dx * cos + dy * sin) / self.zoom;
+                let layer_y = (dy * cos - dx * sin) / self.zoom;
+                if layer_x < 0.0 || layer_y < 0.0 || layer_x >= self.pos_w as f32 || layer_y >= self.pos_h as f32 { continue; }
+                let (layer_x, layer_y) = (layer_x as usize, layer_y as usize);
+                f(self.pixel_data[layer_y * self.pos_w + layer_x], y * width + x, (layer_x, layer_y));
+            }
+        }
+    }
+    /// calls f with the visible pixels of each line of this layer which is in rows (rows.len() pixels of a screen with the given width, starting at first_row),
+    /// the index in rows where they go and where the first of them is in the layer (column, line).
+    fn for_each_visible_line(&self, rows: usize, first_row: usize, width: usize, mut f: impl FnMut(&[Pixel], usize, (usize, usize))) {
+        let (pos_x, pos_y) = (self.pos_x.round() as isize, self.pos_y.round() as isize - first_row as isize);
+        let width_line = width;
+        let row_count = rows / width_line;
+        // only the lines and columns of this layer which are in rows
+        let first_line = (-pos_y).clamp(0, self.pos_h as isize) as usize;
+        let last_line = (row_count as isize - pos_y).clamp(first_line as isize, self.pos_h as isize) as usize;
+        let first_column = (-pos_x).clamp(0, self.pos_w as isize) as usize;
+        let last_column = (width_line as isize - pos_x).clamp(first_column as isize, self.pos_w as isize) as usize;
+        if first_line == last_line || first_column == last_column { return; }
+        let mut line_start_index = (pos_y + first_line as isize) as usize * width_line;
+        for line in first_line..last_line {
+            let index = (line_start_index as isize + pos_x + first_column as isize) as usize;
+            f(&self.line(line)[first_column..last_column], index, (first_column, line));
             line_start_index += width_line;
         }
     }
 }
 
-pub enum Pixel {
-    Transparent,
-    Opaque { r: u8, g: u8, b: u8 },
-    /// rgb values range from 0 to 255, alpha from 0 t 1.
-    SemiTransparent { r: f32, g: f32, b: f32, a: f32 },
+/// a colour with premultiplied alpha: r, g and b are already multiplied with a, so they can't be bigger than a.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Pixel {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
 }
-impl Copy for Pixel {
+impl Pixel {
+    pub const TRANSPARENT: Self = Self { r: 0, g: 0, b: 0, a: 0 };
+    pub const fn opaque(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+    /// a pixel from a colour that isn't premultiplied yet, like the ones in png files.
+    pub fn from_straight_alpha(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r: mul(r, a), g: mul(g, a), b: mul(b, a), a }
+    }
+    /// this pixel multiplied with tint, channel by channel. Pixel::opaque(255, 255, 255) doesn't change anything.
+    pub fn tinted(self, tint: Self) -> Self {
+        Self { r: mul(self.r, tint.r), g: mul(self.g, tint.g), b: mul(self.b, tint.b), a: mul(self.a, tint.a) }
+    }
+    /// this pixel drawn on top of below with the given blend mode.
+    pub fn blend_onto(self, below: Self, blend_mode: BlendMode) -> Self {
+        if blend_mode == BlendMode::Normal && self.a == 255 { return self; }
+        let (own, keep) = self.blend_parts(below.a, blend_mode);
+        own.plus_scaled(below, keep)
+    }
+    /// every blend mode is own + below * keep / 255 (for each channel), which is what lets lit and unlit layers be blended separately.
+    fn blend_parts(self, below_alpha: u8, blend_mode: BlendMode) -> (Self, Self) {
+        let s = self;
+        match blend_mode {
+            BlendMode::Normal => (s, Self { r: 255 - s.a, g: 255 - s.a, b: 255 - s.a, a: 255 - s.a }),
+            BlendMode::Additive => (s, Self { r: 255, g: 255, b: 255, a: 255 }),
+            // s * below + s * (1 - below alpha) + below * (1 - s alpha)
+            BlendMode::Multiply => {
+                let keep = |s: u8| s.saturating_add(255 - self.a);
+                (Self { r: mul(s.r, 255 - below_alpha), g: mul(s.g, 255 - below_alpha), b: mul(s.b, 255 - below_alpha), a: s.a }, Self { r: keep(s.r), g: keep(s.g), b: keep(s.b), a: 255 - s.a })
+            },
+            BlendMode::Screen => (s, Self { r: 255 - s.r, g: 255 - s.g, b: 255 - s.b, a: 255 - s.a }),
+        }
+    }
+    fn plus_scaled(self, other: Self, scale: Self) -> Self {
+        Self {
+            r: self.r.saturating_add(mul(other.r, scale.r)),
+            g: self.g.saturating_add(mul(other.g, scale.g)),
+            b: self.b.saturating_add(mul(other.b, scale.b)),
+            a: self.a.saturating_add(mul(other.a, scale.a)),
+        }
+    }
+}
+
+/// a * b / 255, rounded
+fn mul(a: u8, b: u8) -> u8 {
+    let x = a as u16 * b as u16 + 128;
+    ((x + (x >> 8)) >> 8) as u8
+}
+
+/// light that a layer gives off itself (see Layer.emission), like a lantern, a neon sign or a screen. it is added after lighting, so it
+/// can be seen even where there is no light at all. things drawn on top of the layer cover it like they cover the layer.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum Emission {
+    #[default]
+    None,
+    /// every pixel of the layer glows in its own colour tinted with this one. white looks like the layer is always in full light (on top of the light it gets).
+    Color(Pixel),
+    /// how much each part of the layer glows, as width x height premultiplied colours (see Emission::mask), stretched over the layer.
+    Mask { pixels: Arc<Vec<Pixel>>, width: usize, height: usize, },
 }
-impl Clone for Pixel {
-    fn clone(&self) -> Self {
+impl Emission {
+    /// a mask from an image that is width pixels wide and isn't premultiplied, like Layer::draw_image takes
+    pub fn mask(rgba: &[u8], width: usize) -> Self {
+        let pixels: Vec<Pixel> = rgba.chunks_exact(4).map(|pixel| Pixel::from_straight_alpha(pixel[0], pixel[1], pixel[2], pixel[3])).collect();
+        let height = pixels.len().checked_div(width).unwrap_or(0);
+        Self::Mask { pixels: Arc::new(pixels), width, height }
+    }
+    /// the average colour (r, g, b) of the glowing parts, for lights that come from an emission (see WorldObject::with_emitted_light)
+    pub fn average_color(&self) -> (u8, u8, u8) {
         match self {
-            Self::Transparent => Self::Transparent,
-            Self::Opaque { r, g, b } => Self::Opaque { r: r.clone(), g: g.clone(), b: b.clone() },
-            Self::SemiTransparent { r, g, b, a } => Self::SemiTransparent { r: r.clone(), g: g.clone(), b: b.clone(), a: a.clone() },
+            Self::None => (0, 0, 0),
+            Self::Color(color) => (color.r, color.g, color.b),
+            Self::Mask { pixels, .. } => {
+                let glowing = pixels.iter().filter(|pixel| pixel.a != 0);
+                let (count, r, g, b) = glowing.fold((0u64, 0u64, 0u64, 0u64), |(count, r, g, b), pixel| (count + 1, r + pixel.r as u64, g + pixel.g as u64, b + pixel.b as u64));
+                // nothing glowing adds up to black
+                let count = count.max(1);
+                ((r / count) as u8, (g / count) as u8, (b / count) as u8)
+            },
         }
     }
-}
\ No newline at end of file
+}
+
+/// how an image is fitted into a layer, see Layer::draw_image. the default draws the image stretched to the layer, like before there were transforms.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ImageTransform {
+    /// clockwise, in radians, around the centre of the layer. corners that end up outside of the layer are cut off.
+    pub rotation: f32,
+    /// (1.0, 1.0) fills the layer, (0.5, 0.5) is half as wide and high, in the centre of the layer
+    pub scale: (f32, f32),
+    /// mirrors the image left to right
+    pub flip_h: bool,
+    /// mirrors the image top to bottom
+    pub flip_v: bool,
+    /// every pixel is multiplied with this, see Pixel::tinted
+    pub tint: Pixel,
+    pub filter: ImageFilter,
+}
+impl Default for ImageTransform {
+    fn default() -> Self {
+        Self { rotation: 0.0, scale: (1.0, 1.0), flip_h: false, flip_v: false, tint: Pixel::opaque(255, 255, 255), filter: ImageFilter::Nearest }
+    }
+}
+
+/// how the pixels of a scaled image are picked
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ImageFilter {
+    /// the image pixel closest to the layer pixel. sharp, for pixel art.
+    #[default]
+    Nearest,
+    /// blends the four closest image pixels. smooth when scaling up or rotating.
+    Bilinear,
+    /// the average of all image pixels that end up in a layer pixel. smooth when scaling down, like nearest when scaling up.
+    Box,
+}
+impl ImageFilter {
+    /// the colour at (x, y) of a w x h image, where one layer pixel covers step_x x step_y image pixels.
+    fn sample(self, image: &[Pixel], (w, h): (usize, usize), (x, y): (f32, f32), (step_x, step_y): (f32, f32)) -> Pixel {
+        match self {
+            Self::Box if step_x > 1.0 || step_y > 1.0 => {
+                let range = |center: f32, step: f32, size: usize| {
+                    let half = step.max(1.0) / 2.0;
+                    let first = ((center - half).round().max(0.0) as usize).min(size - 1);
+                    let last = ((center + half).round() as usize).clamp(first + 1, size);
+                    first..last
+                };
+                let (xs, ys) = (range(x, step_x, w), range(y, step_y, h));
+                let count = (xs.len() * ys.len()) as u32;
+                let mut sum = [0u32; 4];
+                for y in ys {
+                    for pixel in image[y * w + xs.start..y * w + xs.end].iter() {
+                        sum[0] += pixel.r as u32;
+                        sum[1] += pixel.g as u32;
+                        sum[2] += pixel.b as u32;
+                        sum[3] += pixel.a as u32;
+                    }
+                }
+                let average = |sum: u32| ((sum + count / 2) / count) as u8;
+                Pixel { r: average(sum[0]), g: average(sum[1]), b: average(sum[2]), a: average(sum[3]) }
+            },
+            Self::Bilinear => {
+                // pixel centres are at .5, the pixels at the edge are repeated outwards
+                let (x, y) = ((x - 0.5).clamp(0.0, (w - 1) as f32), (y - 0.5).clamp(0.0, (h - 1) as f32));
+                let (x0, y0) = (x as usize, y as usize);
+                let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+                let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+                let (p00, p10, p01, p11) = (image[y0 * w + x0], image[y0 * w + x1], image[y1 * w + x0], image[y1 * w + x1]);
+                let mix = |c00: u8, c10: u8, c01: u8, c11: u8| {
+                    let top = c00 as f32 + (c10 as f32 - c00 as f32) * tx;
+                    let bottom = c01 as f32 + (c11 as f32 - c01 as f32) * tx;
+                    (top + (bottom - top) * ty).round() as u8
+                };
+                Pixel { r: mix(p00.r, p10.r, p01.r, p11.r), g: mix(p00.g, p10.g, p01.g, p11.g), b: mix(p00.b, p10.b, p01.b, p11.b), a: mix(p00.a, p10.a, p01.a, p11.a) }
+            },
+            Self::Nearest | Self::Box => image[(y as usize).min(h - 1) * w + (x as usize).min(w - 1)],
+        }
+    }
+}
+
+/// how a layer's pixels are combined with what is already below them
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BlendMode {
+    /// covers what is below, depending on alpha
+    #[default]
+    Normal,
+    /// adds to what is below, for glow and light effects
+    Additive,
+    /// darkens what is below, for shadows and tinted glass
+    Multiply,
+    /// brightens what is below, but never more than to white
+    Screen,
+}
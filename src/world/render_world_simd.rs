@@ -0,0 +1,109 @@
+// simd versions of the hot loops in render_world, used when WorldRenderer::simd is true.
+// the `wide` crate picks the instructions at compile time: simd128 on the web (see .cargo/config.toml), sse2/avx on x86,
+// neon on arm, and plain arrays everywhere else. everything in here returns exactly what the scalar code returns.
+
+use wide::{f32x8, u32x8, CmpGe, CmpGt, CmpLe, CmpLt};
+
+use super::{render_world::{light_visibility, render_joiner::ToneCurve, with_global_lights, MAX_LIGHT}, render_world_layers::Pixel, world::{World, Object::Objects::LightFalloff}};
+
+/// the light at 8 neighbouring light map cells in one row (at world_x, all at world_y), like render_world::light_at.
+pub fn light_at_x8(world_x: [f32; 8], world_y: f32, lights: &[usize], world: &World, shadow_samples: &[Vec<(f32, f32)>]) -> [(u32, u32, u32); 8] {
+    let xs = f32x8::new(world_x);
+    let mut r = u32x8::ZERO;
+    let mut g = u32x8::ZERO;
+    let mut b = u32x8::ZERO;
+    for &light_index in lights {
+        let light_source = &world.lights_rendered[light_index];
+        let dist_x = f32x8::splat(light_source.x) - xs;
+        let dist_y = light_source.y - world_y; // the same for all 8 cells
+
+        let light_source_max_reach = light_source.size + light_source.range;
+        let light_source_max_reach_squared = light_source_max_reach * light_source_max_reach;
+
+        if dist_y.abs() > light_source_max_reach { continue; }
+
+        let dist_squared = dist_x * dist_x + f32x8::splat(dist_y * dist_y);
+
+        let in_reach = dist_squared.cmp_lt(f32x8::splat(light_source_max_reach_squared));
+        if in_reach.none() { continue; }
+
+        // the cone of a spotlight, one cell at a time like in light_at
+        let (in_reach, cone) = match light_source.cone {
+            None => (in_reach, f32x8::ONE),
+            Some(cone) => {
+                let dist_x = dist_x.to_array();
+                let cone = f32x8::new(std::array::from_fn(|i| cone.factor(-dist_x[i], -dist_y)));
+                (in_reach & cone.cmp_gt(f32x8::ZERO), cone)
+            },
+        };
+        if in_reach.none() { continue; }
+
+        // shadows are checked one cell at a time, because each occluder test can stop early
+        let visibility = if world.occluders.is_empty() { f32x8::ONE } else {
+            let in_reach = in_reach.move_mask();
+            f32x8::new(std::array::from_fn(|i| if in_reach & (1 << i) != 0 { light_visibility(world, light_source, world_x[i], world_y, &shadow_samples[light_index]) } else { 0.0 }))
+        };
+        let lit = in_reach & visibility.cmp_gt(f32x8::ZERO);
+
+        let light_source_size_squared = light_source.size * light_source.size;
+        let in_core = dist_squared.cmp_le(f32x8::splat(light_source_size_squared));
+
+        let factor = if let LightFalloff::Quadratic = light_source.falloff {
+            let factor_at_size = light_source_size_squared / light_source_max_reach_squared;
+            let factor = dist_squared / f32x8::splat(light_source_max_reach_squared);
+            let factor = (f32x8::ONE - factor) / f32x8::splat(1.0 - factor_at_size);
+            factor * factor
+        } else {
+            // the other falloffs need a sqrt and aren't used as often, so they are calculated one cell at a time
+            let dist_squared = dist_squared.to_array();
+            f32x8::new(std::array::from_fn(|i| light_source.falloff.factor(dist_squared[i], light_source.size, light_source_max_reach)))
+        };
+        let factor = in_core.blend(f32x8::ONE, factor);
+        let factor = factor * visibility * cone;
+        let factor_int = (factor * f32x8::splat(u16::MAX as f32)).trunc_int().to_array();
+
+        // cells that get the full brightness (in the core, not shadowed and in the bright part of the cone) don't go through factor_int, just like in light_at.
+        let (lit, full) = (lit.move_mask(), (in_core & visibility.cmp_ge(f32x8::ONE) & cone.cmp_ge(f32x8::ONE)).move_mask());
+        let factor_int = u32x8::new(std::array::from_fn(|i| if lit & (1 << i) != 0 { factor_int[i] as u32 } else { 0 }));
+        let full = u32x8::new(std::array::from_fn(|i| if lit & full & (1 << i) != 0 { u32::MAX } else { 0 }));
+
+        // like add_light: the sums stay at or below MAX_LIGHT, so they can't overflow
+        let max_light = u32x8::splat(MAX_LIGHT);
+        let brightness = |brightness: u16| {
+            let brightness = u32x8::splat(brightness as u32);
+            full.blend(brightness, (brightness * factor_int) >> 16_u32)
+        };
+        r = (r + brightness(light_source.brightness.0)).min(max_light);
+        g = (g + brightness(light_source.brightness.1)).min(max_light);
+        b = (b + brightness(light_source.brightness.2)).min(max_light);
+    }
+    let (r, g, b) = (r.to_array(), g.to_array(), b.to_array());
+    std::array::from_fn(|i| with_global_lights((r[i], g[i], b[i]), world_x[i], world_y, world))
+}
+
+/// like render_joiner::join_row: lights one row of objects (see ToneCurve::lit) and writes the rgb values to buffer (4 bytes per pixel, alpha is not changed).
+/// a lit value before the lookup fits into 32 bits, so two pixels fit into one u32x8.
+pub fn join_row(buffer: &mut [u8], objects: &[Pixel], lights: &[(u32, u32, u32)], tone_curve: &ToneCurve) {
+    let pairs = objects.len() / 2;
+    let (to_lit, light_shift, curve) = (&tone_curve.to_lit, tone_curve.light_shift, &tone_curve.curve);
+    for ((buffer, objects), lights) in buffer.chunks_exact_mut(8).zip(objects.chunks_exact(2)).zip(lights.chunks_exact(2)) {
+        let (o0, o1, l0, l1) = (objects[0], objects[1], lights[0], lights[1]);
+        let values = [o0.r, o0.g, o0.b, 0, o1.r, o1.g, o1.b, 0];
+        let lit = ((u32x8::new(values.map(|value| to_lit[value as usize] as u32))
+            * (u32x8::new([l0.0, l0.1, l0.2, 0, l1.0, l1.1, l1.2, 0]) >> light_shift)) >> (16 - light_shift))
+            .to_array();
+        buffer[0] = curve[lit[0] as usize];
+        buffer[1] = curve[lit[1] as usize];
+        buffer[2] = curve[lit[2] as usize];
+        buffer[4] = curve[lit[4] as usize];
+        buffer[5] = curve[lit[5] as usize];
+        buffer[6] = curve[lit[6] as usize];
+    }
+    // the last pixel if the row has an odd length
+    for index in pairs * 2..objects.len() {
+        let (obj, light) = (objects[index], lights[index]);
+        buffer[index * 4] = tone_curve.lit(obj.r, light.0);
+        buffer[index * 4 + 1] = tone_curve.lit(obj.g, light.1);
+        buffer[index * 4 + 2] = tone_curve.lit(obj.b, light.2);
+    }
+}
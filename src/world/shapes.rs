@@ -0,0 +1,108 @@
+use super::render_world_layers::{BlendMode, Layer, Pixel};
+
+/// a shape that is drawn anti-aliased, see WorldObjectData::Shapes. positions are in pixels from the top left corner of the layer.
+/// shapes with an outline are only drawn as a line of that thickness along their edge, the others are filled.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Shape {
+    Circle { x: f32, y: f32, radius: f32, outline: Option<f32>, },
+    Ellipse { x: f32, y: f32, radius_x: f32, radius_y: f32, outline: Option<f32>, },
+    /// (x, y) is the top left corner, radius is the radius of the corners
+    RoundedRect { x: f32, y: f32, w: f32, h: f32, radius: f32, outline: Option<f32>, },
+    /// the corners in order, the last one is connected to the first
+    Polygon { points: Vec<(f32, f32)>, outline: Option<f32>, },
+    /// lines have round ends
+    Line { x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, },
+    /// lines from each point to the next, closed also connects the last point to the first
+    Polyline { points: Vec<(f32, f32)>, thickness: f32, closed: bool, },
+}
+
+/// the distance from (x, y) to the line from a to b
+fn segment_distance(x: f32, y: f32, (ax, ay): (f32, f32), (bx, by): (f32, f32)) -> f32 {
+    let (dx, dy) = (bx - ax, by - ay);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 { 0.0 } else { (((x - ax) * dx + (y - ay) * dy) / length).clamp(0.0, 1.0) };
+    (x - ax - t * dx).hypot(y - ay - t * dy)
+}
+
+/// the lines between neighbouring points, and from the last point to the first if closed
+fn segments(points: &[(f32, f32)], closed: bool) -> impl Iterator<Item = ((f32, f32), (f32, f32))> + '_ {
+    let last = if closed && points.len() > 2 { points.last().map(|&last| (last, points[0])) } else { None };
+    points.windows(2).map(|pair| (pair[0], pair[1])).chain(last)
+}
+
+impl Shape {
+    /// the part of the layer (x0, y0, x1, y1) the shape can be in, in pixels
+    fn bounds(&self) -> (f32, f32, f32, f32) {
+        let points_bounds = |points: &[(f32, f32)], padding: f32| points.iter().fold(
+            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            |(x0, y0, x1, y1), &(x, y)| (x0.min(x - padding), y0.min(y - padding), x1.max(x + padding), y1.max(y + padding)),
+        );
+        let half = |outline: &Option<f32>| outline.map_or(0.0, |thickness| thickness / 2.0);
+        match self {
+            Self::Circle { x, y, radius, outline } => (x - radius - half(outline), y - radius - half(outline), x + radius + half(outline), y + radius + half(outline)),
+            Self::Ellipse { x, y, radius_x, radius_y, outline } => (x - radius_x - half(outline), y - radius_y - half(outline), x + radius_x + half(outline), y + radius_y + half(outline)),
+            Self::RoundedRect { x, y, w, h, outline, .. } => (x - half(outline), y - half(outline), x + w + half(outline), y + h + half(outline)),
+            Self::Polygon { points, outline } => points_bounds(points, half(outline)),
+            Self::Line { x1, y1, x2, y2, thickness } => points_bounds(&[(*x1, *y1), (*x2, *y2)], thickness / 2.0),
+            Self::Polyline { points, thickness, .. } => points_bounds(points, thickness / 2.0),
+        }
+    }
+    /// how far (x, y) is from the edge of the shape, negative inside of it
+    fn signed_distance(&self, x: f32, y: f32) -> f32 {
+        let outlined = |distance: f32, outline: &Option<f32>| match outline {
+            Some(thickness) => distance.abs() - thickness / 2.0,
+            None => distance,
+        };
+        match self {
+            Self::Circle { x: cx, y: cy, radius, outline } => outlined((x - cx).hypot(y - cy) - radius, outline),
+            Self::Ellipse { x: cx, y: cy, radius_x, radius_y, outline } => {
+                // an approximation that is exact on the edge, which is all anti-aliasing needs
+                let (px, py) = (x - cx, y - cy);
+                let k0 = (px / radius_x).hypot(py / radius_y);
+                let k1 = (px / (radius_x * radius_x)).hypot(py / (radius_y * radius_y));
+                let distance = if k1 == 0.0 { -radius_x.min(*radius_y) } else { k0 * (k0 - 1.0) / k1 };
+                outlined(distance, outline)
+            },
+            Self::RoundedRect { x: rx, y: ry, w, h, radius, outline } => {
+                let (half_w, half_h) = (w / 2.0, h / 2.0);
+                let radius = radius.clamp(0.0, half_w.min(half_h));
+                let qx = (x - rx - half_w).abs() - half_w + radius;
+                let qy = (y - ry - half_h).abs() - half_h + radius;
+                let distance = qx.max(0.0).hypot(qy.max(0.0)) + qx.max(qy).min(0.0) - radius;
+                outlined(distance, outline)
+            },
+            Self::Polygon { points, outline } => {
+                let distance = segments(points, true).map(|(a, b)| segment_distance(x, y, a, b)).fold(f32::MAX, f32::min);
+                // even-odd rule: inside if a line to the right crosses the edges an odd number of times
+                let inside = segments(points, true).filter(|&((ax, ay), (bx, by))| {
+                    (ay > y) != (by > y) && x < ax + (y - ay) / (by - ay) * (bx - ax)
+                }).count() % 2 == 1;
+                outlined(if inside { -distance } else { distance }, outline)
+            },
+            Self::Line { x1, y1, x2, y2, thickness } => segment_distance(x, y, (*x1, *y1), (*x2, *y2)) - thickness / 2.0,
+            Self::Polyline { points, thickness, closed } => {
+                segments(points, *closed).map(|(a, b)| segment_distance(x, y, a, b)).fold(f32::MAX, f32::min) - thickness / 2.0
+            },
+        }
+    }
+    /// draws the shape in color on top of what is in layer. pixels that are only partly covered get part of the colour.
+    pub fn draw_onto_layer(&self, layer: &mut Layer, color: Pixel) {
+        let (layer_w, layer_h) = (layer.pos_w, layer.pos_h);
+        let (x0, y0, x1, y1) = self.bounds();
+        // one more pixel on each side for the anti-aliased edge
+        let (x0, x1) = ((x0.floor() - 1.0).clamp(0.0, layer_w as f32) as usize, (x1.ceil() + 1.0).clamp(0.0, layer_w as f32) as usize);
+        let (y0, y1) = ((y0.floor() - 1.0).clamp(0.0, layer_h as f32) as usize, (y1.ceil() + 1.0).clamp(0.0, layer_h as f32) as usize);
+        if x0 >= x1 || y0 >= y1 { return; }
+        let pixels = layer.pixels_mut();
+        for y in y0..y1 {
+            for x in x0..x1 {
+                // the pixel is covered as much as its centre is inside of the shape, fading out over one pixel
+                let coverage = (0.5 - self.signed_distance(x as f32 + 0.5, y as f32 + 0.5)).clamp(0.0, 1.0);
+                if coverage == 0.0 { continue; }
+                let coverage = (coverage * 255.0).round() as u8;
+                let below = &mut pixels[y * layer_w + x];
+                *below = color.tinted(Pixel { r: coverage, g: coverage, b: coverage, a: coverage }).blend_onto(*below, BlendMode::Normal);
+            }
+        }
+    }
+}
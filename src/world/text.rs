@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use super::render_world_layers::{BlendMode, Layer, Pixel};
+
+/// the glyphs text is drawn with
+pub enum Font {
+    /// glyphs of the same size in a grid, see Font::bitmap
+    Bitmap { atlas: Vec<Pixel>, width: usize, glyph_w: usize, glyph_h: usize, chars: Vec<char>, },
+    /// a truetype (or opentype) font, rasterised on the cpu at size pixels
+    TrueType { font: fontdue::Font, size: f32, },
+}
+
+/// one rasterised character, coloured
+struct Glyph {
+    pixels: Vec<Pixel>,
+    width: usize,
+    height: usize,
+    /// where the top left corner of pixels is, from the pen position at the top of the line
+    left: f32,
+    top: f32,
+}
+
+impl Font {
+    /// a font from an atlas image (rgba, not premultiplied) with glyphs of glyph_w x glyph_h pixels, line by line.
+    /// chars are the characters in the atlas in the same order. glyphs should be white, they are tinted with the text colour.
+    pub fn bitmap(rgba: &[u8], width: usize, height: usize, glyph_w: usize, glyph_h: usize, chars: &str) -> Self {
+        let atlas = rgba[..width * height * 4].chunks_exact(4).map(|rgba| Pixel::from_straight_alpha(rgba[0], rgba[1], rgba[2], rgba[3])).collect();
+        Self::Bitmap { atlas, width, glyph_w, glyph_h, chars: chars.chars().collect() }
+    }
+    /// a font from the bytes of a .ttf or .otf file, drawn size pixels high
+    pub fn true_type(bytes: &[u8], size: f32) -> Result<Self, &'static str> {
+        Ok(Self::TrueType { font: fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())?, size })
+    }
+    /// the distance between two lines in pixels
+    pub fn line_height(&self) -> f32 {
+        match self {
+            Self::Bitmap { glyph_h, .. } => *glyph_h as f32,
+            Self::TrueType { font, size } => font.horizontal_line_metrics(*size).map_or(*size, |metrics| metrics.new_line_size),
+        }
+    }
+    /// how far the pen moves after c, in pixels
+    pub fn advance(&self, c: char) -> f32 {
+        match self {
+            Self::Bitmap { glyph_w, .. } => *glyph_w as f32,
+            Self::TrueType { font, size } => font.metrics(c, *size).advance_width,
+        }
+    }
+    fn glyph(&self, c: char, color: Pixel) -> Glyph {
+        match self {
+            Self::Bitmap { atlas, width, glyph_w, glyph_h, chars } => {
+                let Some(index) = chars.iter().position(|&other| other == c) else {
+                    return Glyph { pixels: vec![], width: 0, height: 0, left: 0.0, top: 0.0 };
+                };
+                let columns = width / glyph_w;
+                let (x, y) = (index % columns * glyph_w, index / columns * glyph_h);
+                let pixels = (y..y + glyph_h).flat_map(|y| atlas[y * width + x..y * width + x + glyph_w].iter().map(|pixel| pixel.tinted(color))).collect();
+                Glyph { pixels, width: *glyph_w, height: *glyph_h, left: 0.0, top: 0.0 }
+            },
+            Self::TrueType { font, size } => {
+                let (metrics, coverage) = font.rasterize(c, *size);
+                let ascent = font.horizontal_line_metrics(*size).map_or(*size, |metrics| metrics.ascent);
+                let pixels = coverage.iter().map(|&coverage| color.tinted(Pixel { r: coverage, g: coverage, b: coverage, a: coverage })).collect();
+                // ymin is from the baseline to the bottom of the glyph, upwards
+                Glyph { pixels, width: metrics.width, height: metrics.height, left: metrics.xmin as f32, top: ascent - (metrics.ymin as f32 + metrics.height as f32) }
+            },
+        }
+    }
+}
+
+/// where lines are put inside of the layer of a text object
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// a string drawn with a font, see WorldObjectData::Text. after changing anything but the string (with set), redraw the object (WorldObject::redraw).
+pub struct Text {
+    text: String,
+    pub font: Arc<Font>,
+    /// the text is drawn in this colour, bitmap fonts are tinted with it
+    pub color: Pixel,
+    pub align: TextAlign,
+    /// breaks lines between words (or inside of words that are too long) so they fit into the layer. lines always break at '\n'.
+    pub wrap: bool,
+    /// false if text was changed since the layer was drawn
+    pub(crate) drawn: bool,
+}
+impl Text {
+    pub fn new(text: &str, font: Arc<Font>, color: Pixel) -> Self {
+        Self { text: text.to_string(), font, color, align: TextAlign::Left, wrap: false, drawn: false }
+    }
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    /// changes the text, the object is drawn again in the next frame
+    pub fn set(&mut self, text: &str) {
+        if self.text != text {
+            self.text = text.to_string();
+            self.drawn = false;
+        }
+    }
+    fn width_of(&self, line: &str) -> f32 {
+        line.chars().map(|c| self.font.advance(c)).sum()
+    }
+    /// the lines the text is split into if it is wrapped at wrap_width pixels (or only at '\n' if that is None)
+    pub fn lines(&self, wrap_width: Option<f32>) -> Vec<String> {
+        let mut lines = vec![];
+        for paragraph in self.text.split('\n') {
+            let Some(wrap_width) = wrap_width else {
+                lines.push(paragraph.to_string());
+                continue;
+            };
+            let mut line = String::new();
+            for word in paragraph.split(' ') {
+                let with_word = if line.is_empty() { word.to_string() } else { format!("{line} {word}") };
+                if line.is_empty() || self.width_of(&with_word) <= wrap_width {
+                    line = with_word;
+                } else {
+                    lines.push(std::mem::replace(&mut line, word.to_string()));
+                }
+                // words that don't fit into a line on their own are broken up
+                while self.width_of(&line) > wrap_width && line.chars().count() > 1 {
+                    // as many characters as fit, but at least one
+                    let mut width = 0.0;
+                    let first_len = line.chars().next().map_or(0, char::len_utf8);
+                    let split = line.char_indices().find(|&(_, c)| { width += self.font.advance(c); width > wrap_width })
+                        .map_or(line.len(), |(index, _)| index.max(first_len));
+                    let rest = line.split_off(split);
+                    lines.push(std::mem::replace(&mut line, rest));
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+    /// the width and height of the text in pixels, if it is wrapped at wrap_width pixels
+    pub fn measure(&self, wrap_width: Option<f32>) -> (usize, usize) {
+        let lines = self.lines(wrap_width);
+        let width = lines.iter().map(|line| self.width_of(line)).fold(0.0, f32::max);
+        (width.ceil() as usize, (lines.len() as f32 * self.font.line_height()).ceil() as usize)
+    }
+    /// draws the text into layer, replacing everything. what doesn't fit into the layer is cut off.
+    pub fn draw_onto_layer(&mut self, layer: &mut Layer) {
+        let (layer_w, layer_h) = (layer.pos_w, layer.pos_h);
+        let pixels = layer.pixels_mut();
+        pixels.fill(Pixel::TRANSPARENT);
+        let line_height = self.font.line_height();
+        for (index, line) in self.lines(if self.wrap { Some(layer_w as f32) } else { None }).iter().enumerate() {
+            let mut pen_x = match self.align {
+                TextAlign::Left => 0.0,
+                TextAlign::Center => (layer_w as f32 - self.width_of(line)) / 2.0,
+                TextAlign::Right => layer_w as f32 - self.width_of(line),
+            };
+            let pen_y = index as f32 * line_height;
+            for c in line.chars() {
+                let glyph = self.font.glyph(c, self.color);
+                let (glyph_x, glyph_y) = ((pen_x + glyph.left).round() as isize, (pen_y + glyph.top).round() as isize);
+                for y in 0..glyph.height {
+                    let layer_y = glyph_y + y as isize;
+                    if layer_y < 0 || layer_y >= layer_h as isize { continue; }
+                    for x in 0..glyph.width {
+                        let layer_x = glyph_x + x as isize;
+                        if layer_x < 0 || layer_x >= layer_w as isize { continue; }
+                        let below = &mut pixels[layer_y as usize * layer_w + layer_x as usize];
+                        *below = glyph.pixels[y * glyph.width + x].blend_onto(*below, BlendMode::Normal);
+                    }
+                }
+                pen_x += self.font.advance(c);
+            }
+        }
+        layer.opaque = false;
+        self.drawn = true;
+    }
+}
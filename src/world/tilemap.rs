@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use super::render_world_layers::{BlendMode, Layer, Pixel};
+
+/// the width and height of the chunks a Tilemap is drawn in, in tiles
+pub const CHUNK_SIZE: usize = 16;
+
+/// an image with tiles of the same size in it. the tiles are numbered line by line, starting at 0 in the top left corner.
+pub struct Tileset {
+    /// premultiplied
+    pixels: Vec<Pixel>,
+    width: usize,
+    pub tile_w: usize,
+    pub tile_h: usize,
+    /// how many tiles there are in one line of the image
+    columns: usize,
+} impl Tileset {
+    /// rgba is not premultiplied (like in png files)
+    pub fn new(rgba: &[u8], width: usize, height: usize, tile_w: usize, tile_h: usize) -> Self {
+        let pixels = rgba[..width * height * 4].chunks_exact(4).map(|rgba| Pixel::from_straight_alpha(rgba[0], rgba[1], rgba[2], rgba[3])).collect();
+        Self { pixels, width, tile_w, tile_h, columns: width / tile_w }
+    }
+    /// line y of tile
+    fn tile_line(&self, tile: usize, y: usize) -> &[Pixel] {
+        let (tile_x, tile_y) = (tile % self.columns * self.tile_w, tile / self.columns * self.tile_h);
+        let start = (tile_y + y) * self.width + tile_x;
+        &self.pixels[start..start + self.tile_w]
+    }
+}
+
+/// CHUNK_SIZE x CHUNK_SIZE tiles (less at the right and bottom edge of the map), drawn once and kept until one of the tiles changes
+struct Chunk {
+    /// None if it has to be drawn (again)
+    pixels: Option<Vec<Pixel>>,
+}
+
+/// a grid of tiles from a Tileset, with one or more layers of tiles on top of each other. see WorldObjectData::Tilemap.
+/// only the chunks that are visible are drawn, and changing a tile only draws its chunk again.
+pub struct Tilemap {
+    pub tileset: Arc<Tileset>,
+    /// the size of the map in tiles
+    pub columns: usize,
+    pub rows: usize,
+    /// for each layer, the tile at each position (line by line) or None if it is empty. the first layer is at the bottom.
+    layers: Vec<Vec<Option<usize>>>,
+    chunks: Vec<Chunk>,
+    /// the position of the top left corner of the map on the screen, in pixels. can be negative or between two pixels, like Layer.pos_x.
+    /// objects in the world (see WorldObject::in_world) set it every frame.
+    pub x: f32,
+    pub y: f32,
+    /// the part of the map (x0, y0, x1, y1), in pixels on the map, that is in the layer right now
+    shown: Option<(usize, usize, usize, usize)>,
+    /// how many chunks have been drawn so far
+    pub chunks_drawn: usize,
+} impl Tilemap {
+    /// a map with columns x rows empty tiles in each of layers layers
+    pub fn new(tileset: Arc<Tileset>, columns: usize, rows: usize, layers: usize) -> Self {
+        let chunk_count = columns.div_ceil(CHUNK_SIZE) * rows.div_ceil(CHUNK_SIZE);
+        Self {
+            tileset, columns, rows,
+            layers: vec![vec![None; columns * rows]; layers],
+            chunks: (0..chunk_count).map(|_| Chunk { pixels: None }).collect(),
+            x: 0.0, y: 0.0,
+            shown: None,
+            chunks_drawn: 0,
+        }
+    }
+    pub fn tile(&self, layer: usize, x: usize, y: usize) -> Option<usize> {
+        self.layers[layer][y * self.columns + x]
+    }
+    pub fn set_tile(&mut self, layer: usize, x: usize, y: usize, tile: Option<usize>) {
+        if self.layers[layer][y * self.columns + x] != tile {
+            self.layers[layer][y * self.columns + x] = tile;
+            let chunk = self.chunk_index(x / CHUNK_SIZE, y / CHUNK_SIZE);
+            self.chunks[chunk].pixels = None;
+            self.shown = None;
+        }
+    }
+    /// sets all tiles of a layer at once, tiles has one entry per tile, line by line
+    pub fn set_layer(&mut self, layer: usize, tiles: Vec<Option<usize>>) {
+        assert_eq!(tiles.len(), self.columns * self.rows, "a layer needs exactly one entry per tile");
+        self.layers[layer] = tiles;
+        self.chunks.iter_mut().for_each(|chunk| chunk.pixels = None);
+        self.shown = None;
+    }
+    /// the size of the whole map in pixels
+    pub fn size(&self) -> (usize, usize) {
+        (self.columns * self.tileset.tile_w, self.rows * self.tileset.tile_h)
+    }
+    fn chunk_index(&self, chunk_x: usize, chunk_y: usize) -> usize {
+        chunk_y * self.columns.div_ceil(CHUNK_SIZE) + chunk_x
+    }
+    /// the size of a chunk in pixels
+    fn chunk_size(&self, chunk_x: usize, chunk_y: usize) -> (usize, usize) {
+        let columns = CHUNK_SIZE.min(self.columns - chunk_x * CHUNK_SIZE);
+        let rows = CHUNK_SIZE.min(self.rows - chunk_y * CHUNK_SIZE);
+        (columns * self.tileset.tile_w, rows * self.tileset.tile_h)
+    }
+    fn draw_chunk(&mut self, chunk_x: usize, chunk_y: usize) {
+        let (chunk_w, chunk_h) = self.chunk_size(chunk_x, chunk_y);
+        let (tile_w, tile_h) = (self.tileset.tile_w, self.tileset.tile_h);
+        let mut pixels = vec![Pixel::TRANSPARENT; chunk_w * chunk_h];
+        for layer in self.layers.iter() {
+            for tile_y in 0..chunk_h / tile_h {
+                for tile_x in 0..chunk_w / tile_w {
+                    let Some(tile) = layer[(chunk_y * CHUNK_SIZE + tile_y) * self.columns + chunk_x * CHUNK_SIZE + tile_x] else { continue };
+                    for y in 0..tile_h {
+                        let start = (tile_y * tile_h + y) * chunk_w + tile_x * tile_w;
+                        for (pixel, below) in self.tileset.tile_line(tile, y).iter().zip(pixels[start..start + tile_w].iter_mut()) {
+                            *below = pixel.blend_onto(*below, BlendMode::Normal);
+                        }
+                    }
+                }
+            }
+        }
+        let chunk = self.chunk_index(chunk_x, chunk_y);
+        self.chunks[chunk].pixels = Some(pixels);
+        self.chunks_drawn += 1;
+    }
+    /// draws the part of the map that is on the screen into layer, which is resized to exactly that part and moved to where it is on the screen.
+    /// the map is zoomed and turned with the layer (see Layer.zoom and Layer.rotation) around its top left corner at (x, y).
+    /// the pixels are only drawn again if the part of the map or any of the tiles changed.
+    pub fn draw_onto_layer(&mut self, layer: &mut Layer) {
+        let (map_w, map_h) = self.size();
+        let (zoom, (sin, cos)) = (layer.zoom, layer.rotation.sin_cos());
+        // maps that are neither zoomed nor turned stay on whole pixels, so their pixels end up on the screen as they are
+        let (map_x, map_y) = if zoom == 1.0 && layer.rotation == 0.0 { (self.x.round(), self.y.round()) } else { (self.x, self.y) };
+        // the corners of the screen on the map (turned and scaled back), in pixels on the map
+        let (screen_w, screen_h) = (layer.width as f32, layer.height as f32);
+        let corners = [(0.0, 0.0), (screen_w, 0.0), (0.0, screen_h), (screen_w, screen_h)].map(|(x, y)| {
+            let (dx, dy) = (x - map_x, y - map_y);
+            ((dx * cos + dy * sin) / zoom, (dy * cos - dx * sin) / zoom)
+        });
+        let (min_x, max_x) = corners.iter().fold((f32::MAX, f32::MIN), |(min, max), corner| (min.min(corner.0), max.max(corner.0)));
+        let (min_y, max_y) = corners.iter().fold((f32::MAX, f32::MIN), |(min, max), corner| (min.min(corner.1), max.max(corner.1)));
+        // the part of the map that is on the screen
+        let visible = zoom > 0.0;
+        let map_x0 = if visible { min_x.floor().clamp(0.0, map_w as f32) as usize } else { 0 };
+        let map_x1 = if visible { max_x.ceil().clamp(0.0, map_w as f32) as usize } else { 0 };
+        let map_y0 = if visible { min_y.floor().clamp(0.0, map_h as f32) as usize } else { 0 };
+        let map_y1 = if visible { max_y.ceil().clamp(0.0, map_h as f32) as usize } else { 0 };
+        // and where its top left corner is on the screen
+        let (x0, y0) = (map_x0 as f32 * zoom, map_y0 as f32 * zoom);
+        let (pos_x, pos_y) = (map_x + x0 * cos - y0 * sin, map_y + x0 * sin + y0 * cos);
+        if self.shown == Some((map_x0, map_y0, map_x1, map_y1)) {
+            (layer.pos_x, layer.pos_y) = (pos_x, pos_y);
+            return;
+        }
+        self.shown = Some((map_x0, map_y0, map_x1, map_y1));
+        if map_x0 == map_x1 || map_y0 == map_y1 {
+            layer.resize(pos_x, pos_y, 0, 0);
+            return;
+        }
+        layer.resize(pos_x, pos_y, map_x1 - map_x0, map_y1 - map_y0);
+        let (chunk_px_w, chunk_px_h) = (CHUNK_SIZE * self.tileset.tile_w, CHUNK_SIZE * self.tileset.tile_h);
+        let mut opaque = true;
+        for chunk_y in map_y0 / chunk_px_h..map_y1.div_ceil(chunk_px_h) {
+            for chunk_x in map_x0 / chunk_px_w..map_x1.div_ceil(chunk_px_w) {
+                let chunk = self.chunk_index(chunk_x, chunk_y);
+                if self.chunks[chunk].pixels.is_none() {
+                    self.draw_chunk(chunk_x, chunk_y);
+                }
+                let (chunk_w, chunk_h) = self.chunk_size(chunk_x, chunk_y);
+                let chunk_pixels = self.chunks[chunk].pixels.as_ref().expect("the chunk was just drawn");
+                // the part of this chunk that is visible, in pixels on the map
+                let (cx0, cy0) = ((chunk_x * chunk_px_w).max(map_x0), (chunk_y * chunk_px_h).max(map_y0));
+                let (cx1, cy1) = ((chunk_x * chunk_px_w + chunk_w).min(map_x1), (chunk_y * chunk_px_h + chunk_h).min(map_y1));
+                let layer_w = layer.pos_w;
+                let layer_pixels = layer.pixels_mut();
+                for y in cy0..cy1 {
+                    let from = (y - chunk_y * chunk_px_h) * chunk_w + cx0 - chunk_x * chunk_px_w;
+                    let to = (y - map_y0) * layer_w + cx0 - map_x0;
+                    let line = &chunk_pixels[from..from + cx1 - cx0];
+                    opaque &= line.iter().all(|pixel| pixel.a == 255);
+                    layer_pixels[to..to + cx1 - cx0].copy_from_slice(line);
+                }
+            }
+        }
+        layer.opaque = opaque;
+    }
+}
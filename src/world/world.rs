@@ -1,8 +1,24 @@
+use crate::world::{camera::{Camera, CameraMotion}, render_world_layers::Pixel};
+
 pub struct World {
     pub width: f32,
     pub height: f32,
     pub objects_rendered: Vec<Object::Objects::WorldObject>,
     pub lights_rendered: Vec<Object::Objects::LightObject>,
+    /// lights that reach everything, like sunlight, added to lights_rendered everywhere on the screen
+    pub directional_lights: Vec<Object::Objects::DirectionalLight>,
+    /// light (r, g, b) that reaches every part of the world, even in shadows. u16::MAX is full brightness.
+    pub ambient: (u16, u16, u16),
+    /// shapes that block light, in the same units as lights_rendered
+    pub occluders: Vec<Object::Objects::OccluderObject>,
+    /// drawn behind all objects, every frame. it belongs to RenderLayer::Background.
+    pub background: Background,
+    /// if the objects on each RenderLayer are lit by the lights (indexed by `RenderLayer as usize`, see lighting and set_lighting)
+    pub lit_render_layers: [bool; RenderLayer::ALL.len()],
+    /// what part of the world is on the screen, for lights, occluders and objects placed in the world
+    pub camera: Camera,
+    /// how the camera moves by itself, see update_camera
+    pub camera_motion: CameraMotion,
     pub start_time: wasm_timer::Instant,
 } impl World {
     pub fn new(width: f32, height: f32) -> Self {
@@ -11,9 +27,93 @@ pub struct World {
             height: height,
             objects_rendered: Vec::new(),
             lights_rendered: Vec::new(),
+            directional_lights: Vec::new(),
+            ambient: (0, 0, 0),
+            occluders: Vec::new(),
+            background: Background::Solid { color: (0, 0, 0) },
+            lit_render_layers: RenderLayer::ALL.map(|render_layer| render_layer != RenderLayer::Ui),
+            camera: Camera::default(),
+            camera_motion: CameraMotion::default(),
             start_time: wasm_timer::Instant::now(),
         }
     }
+    /// if the objects on render_layer are lit by the lights. all render layers except RenderLayer::Ui are by default.
+    pub fn lighting(&self, render_layer: RenderLayer) -> bool {
+        self.lit_render_layers[render_layer as usize]
+    }
+    pub fn set_lighting(&mut self, render_layer: RenderLayer, lit: bool) {
+        self.lit_render_layers[render_layer as usize] = lit;
+    }
+    /// moves the camera with camera_motion for a frame that took dt. WorldRenderer::render calls this every frame.
+    pub fn update_camera(&mut self, dt: std::time::Duration) {
+        // the centre of the followed object, in world units
+        let target = self.camera_motion.follow.and_then(|index| self.objects_rendered.get(index)).and_then(|object| {
+            let (x, y) = object.state.world_position?;
+            let world_per_pixel = (2.0 * self.width / (object.state.width as f32 - 1.0), 2.0 * self.height / (object.state.height as f32 - 1.0));
+            let (w, h) = (object.state.layer.pos_w as f32 * object.state.pixel_scale, object.state.layer.pos_h as f32 * object.state.pixel_scale);
+            Some((x + w * world_per_pixel.0 / 2.0, y + h * world_per_pixel.1 / 2.0))
+        });
+        self.camera_motion.update(&mut self.camera, target, dt.as_secs_f32(), (self.width, self.height));
+    }
+    /// a light in the centre of each glowing object which has emitted_light, in the colour of its emission. WorldRenderer::render adds them for one frame.
+    pub fn emitted_lights(&self) -> Vec<Object::Objects::LightObject> {
+        self.objects_rendered.iter().filter_map(|object| {
+            let state = &object.state;
+            let range = state.emitted_light?;
+            let (r, g, b) = state.layer.emission.average_color();
+            if (r, g, b) == (0, 0, 0) { return None; }
+            // the centre of the layer on the screen, like Layer::draw_onto_rows places it
+            let layer = &state.layer;
+            let (half_w, half_h) = (layer.pos_w as f32 * layer.zoom / 2.0, layer.pos_h as f32 * layer.zoom / 2.0);
+            let (sin, cos) = layer.rotation.sin_cos();
+            let centre = (layer.pos_x + half_w * cos - half_h * sin, layer.pos_y + half_w * sin + half_h * cos);
+            let (x, y) = self.camera.screen_to_world(centre, (self.width, self.height), (state.width, state.height));
+            Some(Object::Objects::LightObject::new(x, y, (r as u16 * 257, g as u16 * 257, b as u16 * 257), 0.0, range))
+        }).collect()
+    }
+}
+
+/// groups of objects which are drawn on top of each other in this order. inside of a group, objects are sorted by their z_index.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum RenderLayer {
+    Background,
+    #[default]
+    World,
+    Foreground,
+    /// for menus and the hud, which aren't lit by default
+    Ui,
+} impl RenderLayer {
+    pub const ALL: [Self; 4] = [Self::Background, Self::World, Self::Foreground, Self::Ui];
+}
+
+/// what is visible where there are no (opaque) objects
+pub enum Background {
+    Solid { color: (u8, u8, u8), },
+    /// from top at the first row of the screen to bottom at the last one
+    VerticalGradient { top: (u8, u8, u8), bottom: (u8, u8, u8), },
+    /// an image (rgba, the alpha channel is ignored) repeated over the whole screen, starting in the top left corner.
+    /// it is drawn black if it is empty or rgba has less than width * height pixels.
+    Image { rgba: Vec<u8>, width: usize, height: usize, },
+} impl Background {
+    /// draws columns x0..x1 of row y of a screen that is height pixels high into line (which is the whole row)
+    pub fn draw_onto_line(&self, line: &mut [Pixel], x0: usize, x1: usize, y: usize, height: usize) {
+        match self {
+            Self::Solid { color } => line[x0..x1].fill(Pixel::opaque(color.0, color.1, color.2)),
+            Self::VerticalGradient { top, bottom } => {
+                let t = if height > 1 { y as f32 / (height - 1) as f32 } else { 0.0 };
+                let mix = |top: u8, bottom: u8| (top as f32 + (bottom as f32 - top as f32) * t).round() as u8;
+                line[x0..x1].fill(Pixel::opaque(mix(top.0, bottom.0), mix(top.1, bottom.1), mix(top.2, bottom.2)));
+            },
+            Self::Image { rgba, width, height } if *width > 0 && *height > 0 && rgba.len() >= width * height * 4 => {
+                let img_line = &rgba[(y % height) * width * 4..(y % height + 1) * width * 4];
+                for (x, pixel) in line[x0..x1].iter_mut().enumerate() {
+                    let img_index = (x0 + x) % width * 4;
+                    *pixel = Pixel::opaque(img_line[img_index], img_line[img_index + 1], img_line[img_index + 2]);
+                }
+            },
+            Self::Image { .. } => line[x0..x1].fill(Pixel::opaque(0, 0, 0)),
+        }
+    }
 }
 
 pub mod Object {
@@ -21,15 +121,23 @@ pub mod Object {
     pub mod Objects {
         use std::time::Duration;
 
-        use crate::world::render_world_layers::{Layer, Pixel};
+        use crate::world::render_world_layers::{Emission, ImageTransform, Layer, Pixel};
+        use super::super::{Camera, RenderLayer};
+        use crate::world::{animation::AnimationState, shapes::Shape, text::Text, tilemap::Tilemap};
 
 
         pub struct LightObject {
             pub x: f32,
             pub y: f32,
             pub brightness: (u16, u16, u16),
+            /// the radius of the full-brightness core. bigger lights also cast softer shadows.
             pub size: f32,
+            /// how far the light reaches past size.
             pub range: f32,
+            /// makes this a spotlight which only shines in one direction, see with_cone. None shines everywhere.
+            pub cone: Option<LightCone>,
+            /// how the light gets darker from size to the end of range
+            pub falloff: LightFalloff,
         } impl LightObject {
             pub fn new(x: f32, y: f32, brightness: (u16, u16, u16), size: f32, range: f32) -> Self {
                 Self {
@@ -38,23 +146,234 @@ pub mod Object {
                     brightness: brightness,
                     size: size,
                     range: range,
+                    cone: None,
+                    falloff: LightFalloff::Quadratic,
+                }
+            }
+            /// this light as a spotlight (like a flashlight or a street lamp) shining towards direction (clockwise in radians, 0 is towards +x).
+            /// it has its full brightness up to inner_angle away from direction and fades out until outer_angle.
+            pub fn with_cone(mut self, direction: f32, inner_angle: f32, outer_angle: f32) -> Self {
+                self.cone = Some(LightCone { direction, inner_angle, outer_angle });
+                self
+            }
+            /// this light with a different falloff, see LightFalloff
+            pub fn with_falloff(mut self, falloff: LightFalloff) -> Self {
+                self.falloff = falloff;
+                self
+            }
+        }
+
+        /// how a light gets darker between the edge of its size (full brightness) and the end of its range (no light at all).
+        /// t below is how far a point is along that way, from 0 to 1.
+        #[derive(Clone, PartialEq, Debug)]
+        pub enum LightFalloff {
+            /// (1 - d²/r²) / (1 - s²/r²) squared, where d is the distance, s the size and r size + range. this is the default.
+            Quadratic,
+            /// 1 - t
+            Linear,
+            /// 1 - t² (3 - 2t): soft at both ends
+            Smoothstep,
+            /// like a real light, 1 / (1 + k t²), with k so that it is cutoff at the end of the range. cutoff is subtracted (and the rest
+            /// stretched back to 1), so it reaches 0 there. cutoff is between 0 and 1 (anything outside is clamped to just inside), small values are bright close to the light and dim far from it.
+            InverseSquare { cutoff: f32, },
+            /// the brightness (0 to 1) at evenly spaced points, the first one at t = 0 and the last one at t = 1. points in between are interpolated.
+            Curve(Vec<f32>),
+        } impl LightFalloff {
+            /// how much of the light reaches a point dist_squared (squared) away from a light of size, which reaches max_reach (size + range). from 0 to 1.
+            /// points inside of size always get all of it, so this is only used outside of it.
+            pub fn factor(&self, dist_squared: f32, size: f32, max_reach: f32) -> f32 {
+                // only the other falloffs need the real distance, and sqrt is rather slow
+                let t = || ((dist_squared.sqrt() - size) / (max_reach - size)).clamp(0.0, 1.0);
+                match self {
+                    Self::Quadratic => {
+                        let factor_at_size = size * size / (max_reach * max_reach);
+                        let factor = dist_squared / (max_reach * max_reach); // the sqrt of this factor is the actual factor. The factor is always less than 1.
+                        let factor = (1.0 - factor) /* the width of the outer ring */ / (1.0 - factor_at_size) /* the maximum size of the outer ring (i.e. the value that 1-f1 will have at its insidemost point) */;
+                        factor * factor // this just makes it look a bit nicer, there should be almost no performance impact
+                    },
+                    Self::Linear => 1.0 - t(),
+                    Self::Smoothstep => {
+                        let t = t();
+                        1.0 - t * t * (3.0 - 2.0 * t)
+                    },
+                    Self::InverseSquare { cutoff } => {
+                        let t = t();
+                        // 0 would make k infinite, 1 would divide by 0 below
+                        let cutoff = cutoff.clamp(f32::EPSILON, 1.0 - f32::EPSILON);
+                        let k = 1.0 / cutoff - 1.0;
+                        let brightness = 1.0 / (1.0 + k * t * t);
+                        ((brightness - cutoff) / (1.0 - cutoff)).clamp(0.0, 1.0)
+                    },
+                    Self::Curve(points) => {
+                        let Some(&last) = points.last() else { return 0.0; };
+                        let position = t() * (points.len() - 1) as f32;
+                        let index = position as usize;
+                        let brightness = if index + 1 < points.len() { points[index] + (points[index + 1] - points[index]) * (position - index as f32) } else { last };
+                        brightness.clamp(0.0, 1.0)
+                    },
+                }
+            }
+        }
+
+        /// the part of the world a spotlight shines into, see LightObject::with_cone. angles are in radians.
+        #[derive(Clone, Copy, PartialEq, Debug)]
+        pub struct LightCone {
+            /// where the middle of the cone points, clockwise from +x
+            pub direction: f32,
+            /// how far from direction the light has its full brightness, half the width of the bright part of the cone
+            pub inner_angle: f32,
+            /// how far from direction the light reaches at all, half the width of the whole cone
+            pub outer_angle: f32,
+        } impl LightCone {
+            /// how much of the light reaches (dx, dy) from the light, from 0 to 1. the point the light is at gets all of it.
+            pub fn factor(&self, dx: f32, dy: f32) -> f32 {
+                let dist = (dx * dx + dy * dy).sqrt();
+                if dist == 0.0 { return 1.0; }
+                let (sin, cos) = self.direction.sin_cos();
+                let cos_angle = (dx * cos + dy * sin) / dist;
+                let (cos_inner, cos_outer) = (self.inner_angle.cos(), self.outer_angle.cos());
+                if cos_angle >= cos_inner { 1.0 } else if cos_angle <= cos_outer { 0.0 } else {
+                    // smoothstep, so the edge of the cone has no visible line
+                    let t = (cos_angle - cos_outer) / (cos_inner - cos_outer);
+                    t * t * (3.0 - 2.0 * t)
+                }
+            }
+        }
+
+        /// light from far away that comes from one direction and reaches everything equally, like sunlight. see World.directional_lights.
+        pub struct DirectionalLight {
+            /// the direction the light travels in, clockwise in radians (0 is towards +x). shadows fall in this direction.
+            pub direction: f32,
+            pub brightness: (u16, u16, u16),
+            /// how far the shadows of occluders reach, in world units. 0 casts no shadows.
+            pub shadow_length: f32,
+        } impl DirectionalLight {
+            pub fn new(direction: f32, brightness: (u16, u16, u16), shadow_length: f32) -> Self {
+                Self { direction, brightness, shadow_length }
+            }
+        }
+
+        /// something that light can't pass through. positions are in world units, like LightObject.
+        pub enum OccluderObject {
+            /// an axis-aligned rectangle. (x, y) is the corner with the smallest coordinates.
+            Rectangle { x: f32, y: f32, w: f32, h: f32, },
+            /// an infinitely thin wall from (x1, y1) to (x2, y2).
+            Line { x1: f32, y1: f32, x2: f32, y2: f32, },
+        } impl OccluderObject {
+            /// true if the straight line from (from_x, from_y) to (to_x, to_y) touches this occluder.
+            pub fn blocks(&self, from_x: f32, from_y: f32, to_x: f32, to_y: f32) -> bool {
+                let dx = to_x - from_x;
+                let dy = to_y - from_y;
+                match *self {
+                    Self::Rectangle { x, y, w, h } => {
+                        // slab test: clip the segment's parameter range t (0..1) against both axes
+                        let mut t_min = 0f32;
+                        let mut t_max = 1f32;
+                        for (start, delta, low, high) in [(from_x, dx, x, x + w), (from_y, dy, y, y + h)] {
+                            if delta == 0.0 {
+                                if start < low || start > high { return false; }
+                            } else {
+                                let t1 = (low - start) / delta;
+                                let t2 = (high - start) / delta;
+                                t_min = t_min.max(t1.min(t2));
+                                t_max = t_max.min(t1.max(t2));
+                                if t_min > t_max { return false; }
+                            }
+                        }
+                        true
+                    },
+                    Self::Line { x1, y1, x2, y2 } => {
+                        let ex = x2 - x1;
+                        let ey = y2 - y1;
+                        let denominator = dx * ey - dy * ex;
+                        if denominator == 0.0 { return false; } // parallel
+                        let ox = x1 - from_x;
+                        let oy = y1 - from_y;
+                        let t = (ox * ey - oy * ex) / denominator; // position on the tested segment
+                        let u = (ox * dy - oy * dx) / denominator; // position on the wall
+                        (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)
+                    },
                 }
             }
         }
 
         pub enum WorldObjectData {
             Rectangle { color: Pixel, },
-            Image { rgba: Vec<u8>, width: usize, height: usize, },
+            /// rgba is not premultiplied (like in png files), the pixels of the layer are.
+            /// after changing transform, the object has to be redrawn (WorldObject::redraw).
+            Image { rgba: Vec<u8>, width: usize, height: usize, transform: ImageTransform, },
+            /// shows one frame of a sprite sheet at a time, see AnimationState for playing clips.
+            SpriteSheet { animation: AnimationState, },
+            /// a string, see Text. the layer is the box the text is drawn (and wrapped) in.
+            Text { text: Text, },
+            /// shapes drawn in order on a transparent layer, positions are in pixels inside of the layer.
+            /// after changing shapes, the object has to be redrawn (WorldObject::redraw).
+            Shapes { shapes: Vec<(Shape, Pixel)>, },
+            /// a grid of tiles. the layer is moved and resized to the part of the map that is on the screen, so move the map with tilemap.x and tilemap.y.
+            Tilemap { tilemap: Tilemap, },
+            /// shows the pixels of world.objects_rendered[object], shared with that object instead of copied. they are taken again every frame after the objects have drawn, so it always looks like the object does.
+            /// only the pixels are shared, the blend mode and emission are this object's own. if object is shared itself, this shows the pixels that one shows,
+            /// and without any pixels to show (object is past the end of objects_rendered, or shared objects show each other) this shows nothing.
+            Shared { object: usize, },
         }
         pub struct WorldObject_State {
             pub width: usize,
             pub height: usize,
             pub data: WorldObjectData,
             pub layer: Layer,
+            /// the group this object is drawn with, RenderLayer::World by default
+            pub render_layer: RenderLayer,
+            /// objects with a higher z_index are drawn on top of the others in the same render_layer.
+            /// with the same z_index, objects that were added to the world later are on top.
+            pub z_index: i32,
+            /// for objects placed in the world (see WorldObject::in_world), where the top left corner of the layer is in world units.
+            /// the layer is moved, zoomed and rotated with the camera every frame. None for objects placed on the screen.
+            pub world_position: Option<(f32, f32)>,
+            /// for objects placed in the world, how many screen pixels one pixel of the layer covers at a zoom of 1.
+            /// WorldRenderer::resize changes it, so the objects keep their size in the world.
+            pub pixel_scale: f32,
+            /// for objects placed with new_rel, (pos_x, pos_y, pos_w, pos_h) relative to the screen, so they keep their place when it is resized
+            pub relative: Option<(f32, f32, f32, f32)>,
+            /// how far (in world units) the emission of the layer lights up the world around the object, see WorldObject::with_emitted_light
+            pub emitted_light: Option<f32>,
+        }
+        impl WorldObject_State {
+            /// moves the layer to where the camera shows world_position (see WorldRenderer) and zooms and rotates it with the camera.
+            /// tilemaps are moved with tilemap.x and tilemap.y instead, and move their layer to the part of the map that is on the screen when they are drawn.
+            pub(crate) fn place_in_world(&mut self, camera: &Camera, world_size: (f32, f32)) {
+                let Some(position) = self.world_position else { return };
+                let (x, y) = camera.world_to_screen(position, world_size, (self.width, self.height));
+                if let WorldObjectData::Tilemap { tilemap } = &mut self.data {
+                    (tilemap.x, tilemap.y) = (x, y);
+                } else {
+                    (self.layer.pos_x, self.layer.pos_y) = (x, y);
+                }
+                self.layer.zoom = camera.zoom * self.pixel_scale;
+                self.layer.rotation = camera.rotation();
+            }
+            /// changes the size of the screen this object is on to width x height pixels, see WorldRenderer::resize.
+            /// the object has to be drawn again (draw_init) afterwards.
+            pub(crate) fn resize_screen(&mut self, width: usize, height: usize) {
+                // the world keeps its height, so there are more or less pixels per world unit in the same proportion as there are rows
+                if self.world_position.is_some() && self.height > 1 && height > 1 {
+                    self.pixel_scale *= (height - 1) as f32 / (self.height - 1) as f32;
+                }
+                self.width = width;
+                self.height = height;
+                self.layer.width = width;
+                self.layer.height = height;
+                if let Some((pos_x, pos_y, pos_w, pos_h)) = self.relative {
+                    let (w, h) = (width as f32, height as f32);
+                    let (x, y) = ((pos_x * w).round(), (pos_y * h).round());
+                    self.layer.pos_x_start = x;
+                    self.layer.pos_y_start = y;
+                    self.layer.resize(x, y, (pos_w * w).round() as usize, (pos_h * h).round() as usize);
+                }
+            }
         }
         pub struct WorldObject_Fns {
-            pub draw_init: Box<dyn Fn(&mut WorldObject_State) -> ()>,
-            pub draw_again: Box<dyn Fn(&mut WorldObject_State, &Duration) -> ()>,
+            pub draw_init: Box<dyn Fn(&mut WorldObject_State) -> () + Send + Sync>,
+            pub draw_again: Box<dyn Fn(&mut WorldObject_State, &Duration) -> () + Send + Sync>,
         }
         pub struct WorldObject {
             pub state: WorldObject_State,
@@ -63,14 +382,22 @@ pub mod Object {
             pub fn new_rel(data_and_type: WorldObjectData, pos_x: f32, pos_y: f32, pos_w: f32, pos_h: f32, width: usize, height: usize) -> Self {
                 let w = width as f32;
                 let h = height as f32;
-                Self::new_abs(data_and_type, (pos_x * w).round() as usize, (pos_y * h).round() as usize, (pos_w * w).round() as usize, (pos_h * h).round() as usize, width, height)
+                let mut object = Self::new_abs(data_and_type, (pos_x * w).round() as isize, (pos_y * h).round() as isize, (pos_w * w).round() as usize, (pos_h * h).round() as usize, width, height);
+                object.state.relative = Some((pos_x, pos_y, pos_w, pos_h));
+                object
             }
-            pub fn new_abs(data_and_type: WorldObjectData, pos_x: usize, pos_y: usize, pos_w: usize, pos_h: usize, width: usize, height: usize) -> Self {
+            pub fn new_abs(data_and_type: WorldObjectData, pos_x: isize, pos_y: isize, pos_w: usize, pos_h: usize, width: usize, height: usize) -> Self {
                 let state = WorldObject_State {
                     width: width,
                     height: height,
                     data: data_and_type,
-                    layer: Layer::new(pos_x, pos_y, pos_w, pos_h, width, height),
+                    layer: Layer::new(pos_x as f32, pos_y as f32, pos_w, pos_h, width, height),
+                    render_layer: RenderLayer::World,
+                    z_index: 0,
+                    world_position: None,
+                    pixel_scale: 1.0,
+                    emitted_light: None,
+                    relative: None,
                 };
                 match state.data {
                     WorldObjectData::Rectangle {..} => Self {
@@ -78,13 +405,8 @@ pub mod Object {
                         fns: WorldObject_Fns {
                             draw_init: Box::new(|state: &mut WorldObject_State| {
                                 if let WorldObjectData::Rectangle { color, } = &mut state.data {
-                                    let layer = &mut state.layer;
-                                    for y in 0..layer.pos_h {
-                                        let line = &mut layer.pixel_data[y];
-                                        for x in 0..layer.pos_w {
-                                            line[x] = color.clone();
-                                        }
-                                    }
+                                    state.layer.pixels_mut().fill(*color);
+                                    state.layer.opaque = color.a == 255;
                                 }
                             }),
                             draw_again: Box::new(|state: &mut WorldObject_State, duration: &Duration| {
@@ -95,23 +417,146 @@ pub mod Object {
                         state: state,
                         fns: WorldObject_Fns {
                             draw_init: Box::new(|state: &mut WorldObject_State| {
-                                if let WorldObjectData::Image { rgba, width, height } = &mut state.data {
-                                    for y in 0..state.layer.pos_h {
-                                        let img_index_line = (y * *height / state.layer.pos_h) * *width;
-                                        for x in 0..state.layer.pos_w {
-                                            let img_index = (img_index_line + x * *width / state.layer.pos_w) * 4;
-                                            state.layer.pixel_data[y][x] = Pixel::Opaque { r: rgba[img_index], g: rgba[img_index+1], b: rgba[img_index+2], };
-                                        }
+                                if let WorldObjectData::Image { rgba, width, height, transform } = &mut state.data {
+                                    state.layer.draw_image(rgba, *width, (0, 0, *width, *height), transform);
+                                }
+                            }),
+                            draw_again: Box::new(|_: &mut WorldObject_State, _: &Duration| {}),
+                        }
+                    },
+                    WorldObjectData::SpriteSheet {..} => Self {
+                        state,
+                        fns: WorldObject_Fns {
+                            draw_init: Box::new(|state: &mut WorldObject_State| {
+                                if let WorldObjectData::SpriteSheet { animation } = &mut state.data {
+                                    let frame = animation.frame_at(Duration::ZERO);
+                                    state.layer.draw_image(&animation.sheet.rgba, animation.sheet.width, animation.sheet.frames[frame], &ImageTransform::default());
+                                    animation.shown_frame = Some(frame);
+                                }
+                            }),
+                            draw_again: Box::new(|state: &mut WorldObject_State, duration: &Duration| {
+                                if let WorldObjectData::SpriteSheet { animation } = &mut state.data {
+                                    // the layer is only drawn again when the frame changes
+                                    let frame = animation.frame_at(*duration);
+                                    if animation.shown_frame != Some(frame) {
+                                        state.layer.draw_image(&animation.sheet.rgba, animation.sheet.width, animation.sheet.frames[frame], &ImageTransform::default());
+                                        animation.shown_frame = Some(frame);
+                                    }
+                                }
+                            }),
+                        }
+                    },
+                    WorldObjectData::Text {..} => Self {
+                        state,
+                        fns: WorldObject_Fns {
+                            draw_init: Box::new(|state: &mut WorldObject_State| {
+                                if let WorldObjectData::Text { text } = &mut state.data {
+                                    text.draw_onto_layer(&mut state.layer);
+                                }
+                            }),
+                            draw_again: Box::new(|state: &mut WorldObject_State, _: &Duration| {
+                                if let WorldObjectData::Text { text } = &mut state.data {
+                                    if !text.drawn {
+                                        text.draw_onto_layer(&mut state.layer);
+                                    }
+                                }
+                            }),
+                        }
+                    },
+                    WorldObjectData::Shapes {..} => Self {
+                        state,
+                        fns: WorldObject_Fns {
+                            draw_init: Box::new(|state: &mut WorldObject_State| {
+                                if let WorldObjectData::Shapes { shapes } = &mut state.data {
+                                    state.layer.pixels_mut().fill(Pixel::TRANSPARENT);
+                                    for (shape, color) in shapes.iter() {
+                                        shape.draw_onto_layer(&mut state.layer, *color);
                                     }
                                 }
                             }),
-                            draw_again:  Box::new(|state: &mut WorldObject_State, duration: &Duration| {
-                                state.layer.pos_x = state.layer.pos_x_start + (duration.as_millis() / 10 % 100) as usize;
+                            draw_again: Box::new(|_: &mut WorldObject_State, _: &Duration| {}),
+                        }
+                    },
+                    WorldObjectData::Tilemap {..} => Self {
+                        state,
+                        fns: WorldObject_Fns {
+                            draw_init: Box::new(|state: &mut WorldObject_State| {
+                                if let WorldObjectData::Tilemap { tilemap } = &mut state.data {
+                                    tilemap.draw_onto_layer(&mut state.layer);
+                                }
+                            }),
+                            draw_again: Box::new(|state: &mut WorldObject_State, _: &Duration| {
+                                if let WorldObjectData::Tilemap { tilemap } = &mut state.data {
+                                    tilemap.draw_onto_layer(&mut state.layer);
+                                }
                             }),
                         }
                     },
+                    WorldObjectData::Shared {..} => Self {
+                        state,
+                        fns: WorldObject_Fns {
+                            draw_init: Box::new(|_: &mut WorldObject_State| {}),
+                            draw_again: Box::new(|_: &mut WorldObject_State, _: &Duration| {}),
+                        }
+                    },
                 }
             }
+            /// draws the object again from the start (calls draw_init), for example after its data has been changed
+            pub fn redraw(&mut self) {
+                (self.fns.draw_init)(&mut self.state);
+            }
+            /// the animation of a WorldObjectData::SpriteSheet object, to play, stop or switch clips
+            pub fn animation(&mut self) -> Option<&mut AnimationState> {
+                match &mut self.state.data {
+                    WorldObjectData::SpriteSheet { animation } => Some(animation),
+                    _ => None,
+                }
+            }
+            /// the text of a WorldObjectData::Text object, to change it
+            pub fn text(&mut self) -> Option<&mut Text> {
+                match &mut self.state.data {
+                    WorldObjectData::Text { text } => Some(text),
+                    _ => None,
+                }
+            }
+            /// the map of a WorldObjectData::Tilemap object, to change tiles or move it
+            pub fn tilemap(&mut self) -> Option<&mut Tilemap> {
+                match &mut self.state.data {
+                    WorldObjectData::Tilemap { tilemap } => Some(tilemap),
+                    _ => None,
+                }
+            }
+            /// an object showing tilemap on a width x height screen
+            pub fn new_tilemap(tilemap: Tilemap, width: usize, height: usize) -> Self {
+                Self::new_abs(WorldObjectData::Tilemap { tilemap, }, 0, 0, 0, 0, width, height)
+            }
+            /// this object, moved to render_layer and z_index.
+            pub fn on_render_layer(mut self, render_layer: RenderLayer, z_index: i32) -> Self {
+                self.state.render_layer = render_layer;
+                self.state.z_index = z_index;
+                self
+            }
+            /// this object, placed in the world with the top left corner of its layer at (x, y) in world units, like lights.
+            /// the layer is then moved, zoomed and rotated with World.camera.
+            pub fn in_world(mut self, x: f32, y: f32) -> Self {
+                self.state.world_position = Some((x, y));
+                self
+            }
+            /// this object, glowing with emission (see Emission). shared objects glow like the object they look like.
+            pub fn with_emission(mut self, emission: Emission) -> Self {
+                self.state.layer.emission = emission;
+                self
+            }
+            /// this object, also lighting up the world around it up to range (in world units) in the colour of its emission, like a light in its centre.
+            /// if the object is lit, its own light makes it brighter too.
+            pub fn with_emitted_light(mut self, range: f32) -> Self {
+                self.state.emitted_light = Some(range);
+                self
+            }
+            /// an object at (pos_x, pos_y) which looks like world.objects_rendered[object], see WorldObjectData::Shared.
+            pub fn new_shared(object: usize, pos_x: isize, pos_y: isize, width: usize, height: usize) -> Self {
+                Self::new_abs(WorldObjectData::Shared { object, }, pos_x, pos_y, 0, 0, width, height)
+            }
         }
 
     }
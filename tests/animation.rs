@@ -0,0 +1,99 @@
+use std::{sync::Arc, time::Duration};
+
+use rust_wasm_test_game::world::{animation::{AnimationClip, AnimationState, LoopMode, SpriteSheet}, render_world_layers::Pixel, world::Object::Objects::{WorldObject, WorldObjectData}};
+
+const FRAME: Duration = Duration::from_millis(100);
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+// 4 frames of 2x2 pixels next to each other: red, green, blue, white
+fn sheet() -> SpriteSheet {
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 255, 255]];
+    let mut rgba = vec![];
+    for _ in 0..2 {
+        for color in colors {
+            rgba.extend_from_slice(&color);
+            rgba.extend_from_slice(&color);
+        }
+    }
+    SpriteSheet::grid(rgba, 8, 2, 2, 2)
+        .with_clip("walk", AnimationClip::uniform(0..4, FRAME, LoopMode::Loop))
+        .with_clip("attack", AnimationClip::uniform([3, 2], FRAME, LoopMode::Once))
+}
+
+fn character() -> WorldObject {
+    let mut object = WorldObject::new_abs(WorldObjectData::SpriteSheet { animation: AnimationState::new(Arc::new(sheet())) }, 0, 0, 4, 4, 64, 64);
+    (object.fns.draw_init)(&mut object.state);
+    object
+}
+
+// draws the object at time and returns the colour it shows
+fn color_at(object: &mut WorldObject, time: Duration) -> Pixel {
+    (object.fns.draw_again)(&mut object.state, &time);
+    object.state.layer.line(0)[0]
+}
+
+const RED: Pixel = Pixel::opaque(255, 0, 0);
+const GREEN: Pixel = Pixel::opaque(0, 255, 0);
+const BLUE: Pixel = Pixel::opaque(0, 0, 255);
+const WHITE: Pixel = Pixel::opaque(255, 255, 255);
+
+#[test]
+fn grid_frames() {
+    assert_eq!(sheet().frames, vec![(0, 0, 2, 2), (2, 0, 2, 2), (4, 0, 2, 2), (6, 0, 2, 2)]);
+}
+
+#[test]
+fn loop_modes() {
+    let frames = |loop_mode| {
+        let clip = AnimationClip::new(vec![(0, FRAME), (1, FRAME * 2), (2, FRAME)], loop_mode);
+        (0..12).map(|i| clip.frame_at(ms(i * 50 + 10))).collect::<Vec<_>>()
+    };
+    assert_eq!(frames(LoopMode::Loop), vec![0, 0, 1, 1, 1, 1, 2, 2, 0, 0, 1, 1]);
+    assert_eq!(frames(LoopMode::Once), vec![0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2]);
+    assert_eq!(frames(LoopMode::PingPong), vec![0, 0, 1, 1, 1, 1, 2, 2, 1, 1, 1, 1]);
+    // a clip without frames or time doesn't panic
+    assert_eq!(AnimationClip::new(vec![], LoopMode::Loop).frame_at(FRAME), 0);
+    assert_eq!(AnimationClip::new(vec![(3, Duration::ZERO)], LoopMode::PingPong).frame_at(FRAME), 3);
+}
+
+#[test]
+fn clips_play_from_the_world_clock() {
+    let mut object = character();
+    // the first frame of the sheet until something is played
+    assert_eq!(color_at(&mut object, ms(5000)), RED);
+    object.animation().unwrap().play("walk");
+    // the clip starts at the next frame that is drawn
+    assert_eq!(color_at(&mut object, ms(10000)), RED);
+    assert_eq!(color_at(&mut object, ms(10150)), GREEN);
+    assert_eq!(color_at(&mut object, ms(10250)), BLUE);
+    assert_eq!(color_at(&mut object, ms(10450)), RED);
+    // playing the same clip again doesn't restart it
+    object.animation().unwrap().play("walk");
+    assert_eq!(color_at(&mut object, ms(10550)), GREEN);
+}
+
+#[test]
+fn clips_can_be_stopped_and_switched() {
+    let mut object = character();
+    object.animation().unwrap().play("walk");
+    assert_eq!(color_at(&mut object, ms(0)), RED);
+    assert_eq!(color_at(&mut object, ms(250)), BLUE);
+    object.animation().unwrap().stop();
+    assert!(!object.animation().unwrap().is_playing());
+    assert_eq!(color_at(&mut object, ms(1000)), BLUE);
+
+    object.animation().unwrap().play("attack");
+    assert_eq!(object.animation().unwrap().clip(), Some("attack"));
+    assert_eq!(color_at(&mut object, ms(2000)), WHITE);
+    assert_eq!(color_at(&mut object, ms(2100)), BLUE);
+    // attack plays once, then stays on its last frame
+    assert_eq!(color_at(&mut object, ms(9000)), BLUE);
+}
+
+#[test]
+fn only_animation_objects_have_an_animation() {
+    assert!(WorldObject::new_abs(WorldObjectData::Rectangle { color: RED }, 0, 0, 1, 1, 64, 64).animation().is_none());
+}
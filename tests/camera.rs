@@ -0,0 +1,228 @@
+mod common;
+
+use std::{f32::consts::PI, sync::Arc};
+
+use common::{FULL, SCREEN, WHITE};
+use rust_wasm_test_game::{renderer_headless, world::{camera::{Camera, CameraMotion}, render_world::WorldRenderer, render_world_layers::{Layer, Pixel}, tilemap::{Tilemap, Tileset}, world::Object::Objects::{LightObject, WorldObject, WorldObjectData}}};
+
+const RED: Pixel = Pixel::opaque(255, 0, 0);
+const T: Pixel = Pixel::TRANSPARENT;
+
+// a world of -31.5..31.5 on a 64x64 screen, so one world unit is one pixel and (0, 0) is at pixel (31.5, 31.5)
+const WORLD: (f32, f32) = (31.5, 31.5);
+
+fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+    (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3
+}
+
+// a white object and a red 4x4 square in the world, lit by a light next to them. everything is moved by (dx, dy) world units.
+fn scene(dx: f32, dy: f32, camera: Camera) -> WorldRenderer {
+    let mut world_renderer = common::renderer(WORLD.0, SCREEN, vec![
+        WorldObject::new_abs(WorldObjectData::Rectangle { color: WHITE }, 0, 0, 40, 40, 64, 64).in_world(dx - 20.0, dy - 20.0),
+        WorldObject::new_abs(WorldObjectData::Rectangle { color: RED }, 0, 0, 4, 4, 64, 64).in_world(dx + 2.0, dy - 2.0),
+    ], vec![LightObject::new(dx - 6.0, dy, FULL, 2.0, 20.0)]);
+    world_renderer.world.camera = camera;
+    world_renderer.init();
+    world_renderer
+}
+
+#[test]
+fn world_to_screen_and_back() {
+    let camera = Camera::default();
+    assert_eq!(camera.world_to_screen((0.0, 0.0), WORLD, SCREEN), (31.5, 31.5));
+    assert_eq!(camera.world_to_screen((-31.5, 31.5), WORLD, SCREEN), (0.0, 63.0));
+    let camera = Camera { x: 10.0, y: -4.0, zoom: 2.0, ..Default::default() };
+    assert_eq!(camera.world_to_screen((10.0, -4.0), WORLD, SCREEN), (31.5, 31.5));
+    assert_eq!(camera.world_to_screen((11.0, -4.0), WORLD, SCREEN), (33.5, 31.5));
+    // turned clockwise: what was to the right of the centre is now below it
+    let camera = Camera { rotation: PI / 2.0, ..Default::default() };
+    assert!(close(camera.world_to_screen((1.0, 0.0), WORLD, SCREEN), (31.5, 32.5)));
+    let camera = Camera { x: 3.0, y: -2.0, zoom: 1.7, rotation: 0.7, shake: (0.2, -0.1, 0.05) };
+    for point in [(0.0, 0.0), (-12.5, 8.0), (30.0, 30.0)] {
+        assert!(close(camera.screen_to_world(camera.world_to_screen(point, WORLD, SCREEN), WORLD, SCREEN), point));
+    }
+}
+
+#[test]
+fn scrolling_moves_lights_and_objects_together() {
+    let still = renderer_headless::render_to_image(&mut scene(0.0, 0.0, Camera::default())).0;
+    let scrolled = renderer_headless::render_to_image(&mut scene(8.0, -4.0, Camera { x: 8.0, y: -4.0, ..Default::default() })).0;
+    assert_eq!(still, scrolled);
+    // the red square is lit
+    let red = still.get_pixel(35, 31).0;
+    assert!(red[0] > 100 && red[1] == 0, "{red:?}");
+    // without moving the camera, the scene moved. the light map cells are in other places, so the light is only about the same.
+    let moved = renderer_headless::render_to_image(&mut scene(8.0, -4.0, Camera::default())).0;
+    assert_ne!(still, moved);
+    let moved_red = moved.get_pixel(35 + 8, 31 - 4).0;
+    assert!(moved_red[0].abs_diff(red[0]) < 8 && moved_red[1] == 0, "{moved_red:?}");
+}
+
+#[test]
+fn objects_are_zoomed_and_rotated_with_the_camera() {
+    let mut world_renderer = scene(0.0, 0.0, Camera { zoom: 2.0, rotation: 0.5, ..Default::default() });
+    renderer_headless::render_to_image(&mut world_renderer);
+    let layer = &world_renderer.world.objects_rendered[1].state.layer;
+    assert_eq!((layer.zoom, layer.rotation), (2.0, 0.5));
+    // objects on the screen stay where they are
+    world_renderer.world.objects_rendered.push(WorldObject::new_abs(WorldObjectData::Rectangle { color: RED }, 3, 4, 2, 2, 64, 64));
+    renderer_headless::render_to_image(&mut world_renderer);
+    let layer = &world_renderer.world.objects_rendered[2].state.layer;
+    assert_eq!((layer.pos_x, layer.pos_y, layer.zoom, layer.rotation), (3.0, 4.0, 1.0, 0.0));
+}
+
+#[test]
+fn zoomed_and_rotated_layers() {
+    let draw = |layer: &Layer| {
+        let mut screen = vec![T; 8 * 8];
+        layer.draw_onto(&mut screen, 8, 8);
+        screen
+    };
+    let mut layer = Layer::new(2.0, 2.0, 2, 1, 8, 8);
+    layer.pixels_mut().fill(RED);
+    layer.zoom = 2.0;
+    let screen = draw(&layer);
+    let red: Vec<usize> = (0..64).filter(|&index| screen[index] == RED).collect();
+    assert_eq!(red, [18, 19, 20, 21, 26, 27, 28, 29]);
+    // a quarter turn clockwise around the top left corner: the line goes down, left of where it starts
+    layer.zoom = 1.0;
+    layer.rotation = PI / 2.0;
+    let screen = draw(&layer);
+    let red: Vec<usize> = (0..64).filter(|&index| screen[index] == RED).collect();
+    assert_eq!(red, [2 * 8 + 1, 3 * 8 + 1]);
+    // zoomed and rotated layers never hide the background
+    layer.opaque = true;
+    assert_eq!(layer.opaque_area(8, 8), None);
+}
+
+#[test]
+fn lights_are_rotated_with_the_camera() {
+    let mut world_renderer = common::scene(WORLD.0, SCREEN, vec![common::backdrop(WHITE, SCREEN)], vec![LightObject::new(16.0, 0.0, FULL, 2.0, 6.0)]);
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert!(image.get_pixel(47, 31).0[0] > 200 && image.get_pixel(31, 47).0[0] == 0);
+    world_renderer.world.camera.rotation = PI / 2.0;
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert!(image.get_pixel(31, 47).0[0] > 200 && image.get_pixel(47, 31).0[0] == 0);
+}
+
+#[test]
+fn tilemaps_in_the_world_scroll_with_the_camera() {
+    let tileset = Arc::new(Tileset::new(&[255, 0, 0, 255], 1, 1, 1, 1));
+    let mut tilemap = Tilemap::new(tileset, 4, 4, 1);
+    tilemap.set_layer(0, vec![Some(0); 16]);
+    let mut world_renderer = common::renderer(WORLD.0, SCREEN, vec![WorldObject::new_tilemap(tilemap, 64, 64).in_world(0.5, 0.5)], vec![]);
+    world_renderer.world.camera.x = -10.0;
+    world_renderer.init();
+    renderer_headless::render_to_image(&mut world_renderer);
+    let layer = &world_renderer.world.objects_rendered[0].state.layer;
+    assert_eq!((layer.pos_x, layer.pos_y, layer.pos_w, layer.zoom), (42.0, 32.0, 4, 1.0));
+    assert_eq!(layer.line(0)[0], RED);
+}
+
+#[test]
+fn tilemaps_in_the_world_are_zoomed_and_rotated_with_the_camera() {
+    // white tiles with a red one at (12, 4), which a small light shines on
+    let tileset = Arc::new(Tileset::new(&[255, 255, 255, 255, 255, 0, 0, 255], 2, 1, 1, 1));
+    let mut tilemap = Tilemap::new(tileset, 16, 16, 1);
+    tilemap.set_layer(0, vec![Some(0); 256]);
+    tilemap.set_tile(0, 12, 4, Some(1));
+    let red_tile = (-8.0 + 12.5, -8.0 + 4.5);
+    let mut world_renderer = common::renderer(WORLD.0, SCREEN, vec![WorldObject::new_tilemap(tilemap, 64, 64).in_world(-8.0, -8.0)], vec![LightObject::new(red_tile.0, red_tile.1, FULL, 1.0, 2.0)]);
+    world_renderer.world.camera = Camera { x: 2.0, y: -1.0, zoom: 2.0, rotation: 0.7, ..Default::default() };
+    world_renderer.init();
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    let layer = &world_renderer.world.objects_rendered[0].state.layer;
+    assert_eq!((layer.zoom, layer.rotation), (2.0, 0.7));
+    // the red tile is still under its light, and the white ones far away from it are dark
+    let (x, y) = world_renderer.world.camera.world_to_screen(red_tile, WORLD, SCREEN);
+    let red = image.get_pixel(x as u32, y as u32).0;
+    assert!(red[0] > 100 && red[1] == 0, "{red:?}");
+    let (x, y) = world_renderer.world.camera.world_to_screen((-6.5, 6.5), WORLD, SCREEN);
+    assert!(image.get_pixel(x as u32, y as u32).0[0] < 10);
+}
+
+fn close_to(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+#[test]
+fn following_with_smoothing_and_a_dead_zone() {
+    let mut camera = Camera::default();
+    let mut motion = CameraMotion { smoothing: 0.5, ..Default::default() };
+    motion.update(&mut camera, Some((10.0, -4.0)), 0.5, WORLD);
+    assert!(close_to(camera.x, 10.0 * (1.0 - (-1.0f32).exp())) && close_to(camera.y, -4.0 * (1.0 - (-1.0f32).exp())), "{camera:?}");
+    for _ in 0..100 {
+        motion.update(&mut camera, Some((10.0, -4.0)), 0.1, WORLD);
+    }
+    assert!(close((camera.x, camera.y), (10.0, -4.0)));
+    // without smoothing, the camera is where it should be right away. it only moves as far as the target left the dead zone.
+    let mut camera = Camera::default();
+    let mut motion = CameraMotion { dead_zone: (2.0, 1.0), ..Default::default() };
+    motion.update(&mut camera, Some((1.5, -0.5)), 0.1, WORLD);
+    assert_eq!((camera.x, camera.y), (0.0, 0.0));
+    motion.update(&mut camera, Some((5.0, -3.0)), 0.1, WORLD);
+    assert_eq!((camera.x, camera.y), (3.0, -2.0));
+    // nothing to follow
+    motion.update(&mut camera, None, 0.1, WORLD);
+    assert_eq!((camera.x, camera.y), (3.0, -2.0));
+}
+
+#[test]
+fn the_camera_stays_inside_of_the_level() {
+    let mut camera = Camera { x: 100.0, y: -100.0, ..Default::default() };
+    let mut motion = CameraMotion { bounds: Some((-50.0, -40.0, 50.0, 40.0)), ..Default::default() };
+    motion.update(&mut camera, None, 0.1, WORLD);
+    assert_eq!((camera.x, camera.y), (50.0 - 31.5, -40.0 + 31.5));
+    // zoomed in, less of the level is visible, so the camera can get closer to its edge
+    camera.zoom = 2.0;
+    motion.update(&mut camera, Some((100.0, 0.0)), 0.1, WORLD);
+    assert_eq!(camera.x, 50.0 - 31.5 / 2.0);
+    // a level smaller than the screen is kept in the centre
+    motion.bounds = Some((0.0, 0.0, 20.0, 10.0));
+    motion.update(&mut camera, Some((100.0, 0.0)), 0.1, WORLD);
+    assert_eq!((camera.x, camera.y), (10.0, 5.0));
+}
+
+#[test]
+fn trauma_shakes_the_screen_and_wears_off() {
+    let mut camera = Camera::default();
+    let mut motion = CameraMotion { trauma_decay: 0.5, max_shake_offset: 2.0, max_shake_angle: 0.1, ..Default::default() };
+    motion.add_trauma(0.6);
+    motion.add_trauma(0.6);
+    assert_eq!(motion.trauma, 1.0);
+    let mut shakes = vec![];
+    for _ in 0..10 {
+        motion.update(&mut camera, None, 0.05, WORLD);
+        shakes.push(camera.shake);
+        assert!(camera.shake.0.abs() <= 2.0 && camera.shake.1.abs() <= 2.0 && camera.shake.2.abs() <= 0.1, "{:?}", camera.shake);
+    }
+    // it moves around, but the camera itself stays where it is
+    assert!(shakes.windows(2).all(|pair| pair[0] != pair[1]));
+    assert_eq!((camera.x, camera.y, camera.rotation), (0.0, 0.0, 0.0));
+    assert!(close_to(motion.trauma, 0.75));
+    // everything in the world shakes with the camera
+    assert!(close(camera.world_to_view(camera.shake.0, camera.shake.1), (0.0, 0.0)));
+    assert_eq!(camera.rotation(), camera.shake.2);
+    for _ in 0..40 {
+        motion.update(&mut camera, None, 0.05, WORLD);
+    }
+    assert_eq!((motion.trauma, camera.shake), (0.0, (0.0, 0.0, 0.0)));
+}
+
+#[test]
+fn the_camera_follows_an_object() {
+    let mut world_renderer = common::renderer(WORLD.0, SCREEN, vec![WorldObject::new_abs(WorldObjectData::Rectangle { color: RED }, 0, 0, 4, 4, 64, 64).in_world(10.0, -6.0)], vec![]);
+    world_renderer.world.camera_motion.follow = Some(0);
+    world_renderer.init();
+    renderer_headless::render_to_image(&mut world_renderer);
+    // one world unit is one pixel, so the centre of the object is 2 units from its corner
+    let camera = &world_renderer.world.camera;
+    assert_eq!((camera.x, camera.y), (12.0, -4.0));
+    let layer = &world_renderer.world.objects_rendered[0].state.layer;
+    assert_eq!((layer.pos_x, layer.pos_y), (29.5, 29.5));
+    // objects on the screen can't be followed
+    world_renderer.world.objects_rendered.push(WorldObject::new_abs(WorldObjectData::Rectangle { color: RED }, 0, 0, 4, 4, 64, 64));
+    world_renderer.world.camera_motion.follow = Some(1);
+    world_renderer.world.update_camera(std::time::Duration::from_millis(16));
+    assert_eq!((world_renderer.world.camera.x, world_renderer.world.camera.y), (12.0, -4.0));
+}
@@ -0,0 +1,30 @@
+// the scenes the rendering tests are set up with. not every test file uses all of it.
+#![allow(dead_code)]
+
+use rust_wasm_test_game::world::{render_world::WorldRenderer, render_world_layers::Pixel, world::{World, Object::Objects::{LightObject, WorldObject, WorldObjectData}}};
+
+pub const WHITE: Pixel = Pixel::opaque(255, 255, 255);
+pub const FULL: (u16, u16, u16) = (u16::MAX, u16::MAX, u16::MAX);
+/// the size of the screen most tests render
+pub const SCREEN: (usize, usize) = (64, 64);
+
+// a screen of (width, height) pixels showing a world of -size..size on both axes, with objects and lights.
+// on a 64x64 screen a size of 31.5 is one world unit per pixel, with (0, 0) at pixel (31.5, 31.5).
+pub fn renderer(size: f32, (width, height): (usize, usize), objects: Vec<WorldObject>, lights: Vec<LightObject>) -> WorldRenderer {
+    let mut world_renderer = WorldRenderer::new(World::new(size, size), width, height);
+    world_renderer.world.objects_rendered = objects;
+    world_renderer.world.lights_rendered = lights;
+    world_renderer
+}
+
+// like renderer, with the objects drawn (init), so it is ready to render
+pub fn scene(size: f32, screen: (usize, usize), objects: Vec<WorldObject>, lights: Vec<LightObject>) -> WorldRenderer {
+    let mut world_renderer = renderer(size, screen, objects, lights);
+    world_renderer.init();
+    world_renderer
+}
+
+// a rectangle covering the whole screen
+pub fn backdrop(color: Pixel, (width, height): (usize, usize)) -> WorldObject {
+    WorldObject::new_rel(WorldObjectData::Rectangle { color }, 0.0, 0.0, 1.0, 1.0, width, height)
+}
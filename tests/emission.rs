@@ -0,0 +1,86 @@
+mod common;
+
+use common::{SCREEN, WHITE};
+use rust_wasm_test_game::{renderer_headless, world::{render_world::WorldRenderer, render_world_layers::{Emission, Pixel}, world::{RenderLayer, Object::Objects::{LightObject, WorldObject, WorldObjectData}}}};
+
+const ORANGE: Pixel = Pixel::opaque(240, 120, 20);
+
+// a dark 64x64 screen showing a world of -31.5..31.5 (one world unit per pixel)
+fn scene(objects: Vec<WorldObject>) -> WorldRenderer {
+    common::scene(31.5, SCREEN, objects, vec![])
+}
+
+fn at(world_renderer: &mut WorldRenderer, x: u32, y: u32) -> [u8; 4] {
+    renderer_headless::render_to_image(world_renderer).0.get_pixel(x, y).0
+}
+
+fn lantern(emission: Emission) -> WorldObject {
+    WorldObject::new_abs(WorldObjectData::Rectangle { color: ORANGE }, 20, 20, 20, 20, 64, 64).with_emission(emission)
+}
+
+#[test]
+fn glowing_objects_can_be_seen_in_the_dark() {
+    let mut world_renderer = scene(vec![lantern(Emission::None)]);
+    assert_eq!(at(&mut world_renderer, 30, 30), [0, 0, 0, 255]);
+    world_renderer.world.objects_rendered[0].state.layer.emission = Emission::Color(WHITE);
+    assert_eq!(at(&mut world_renderer, 30, 30), [240, 120, 20, 255]);
+    assert_eq!(at(&mut world_renderer, 10, 10), [0, 0, 0, 255]);
+    // a darker emission colour glows less
+    world_renderer.world.objects_rendered[0].state.layer.emission = Emission::Color(Pixel::opaque(128, 128, 128));
+    assert_eq!(at(&mut world_renderer, 30, 30), [120, 60, 10, 255]);
+    // it is added to the light the object gets
+    world_renderer.world.lights_rendered.push(LightObject::new(0.0, 0.0, (u16::MAX / 2, u16::MAX / 2, u16::MAX / 2), 40.0, 1.0));
+    assert_eq!(at(&mut world_renderer, 30, 30), [239, 119, 19, 255]);
+}
+
+#[test]
+fn emission_masks_glow_in_parts() {
+    // the left half of the lantern glows red, the right half doesn't glow
+    let mask = Emission::mask(&[255, 0, 0, 255, 0, 0, 0, 0], 2);
+    let mut world_renderer = scene(vec![lantern(mask)]);
+    assert_eq!(at(&mut world_renderer, 25, 30), [255, 0, 0, 255]);
+    assert_eq!(at(&mut world_renderer, 35, 30), [0, 0, 0, 255]);
+    assert_eq!(Emission::mask(&[255, 0, 0, 255, 0, 0, 0, 0], 2).average_color(), (255, 0, 0));
+}
+
+#[test]
+fn things_on_top_cover_the_glow() {
+    let mut world_renderer = scene(vec![
+        lantern(Emission::Color(WHITE)),
+        WorldObject::new_abs(WorldObjectData::Rectangle { color: WHITE }, 30, 20, 10, 20, 64, 64),
+    ]);
+    assert_eq!(at(&mut world_renderer, 25, 30), [240, 120, 20, 255]);
+    assert_eq!(at(&mut world_renderer, 35, 30), [0, 0, 0, 255]);
+    // the glow is on top of the object itself on unlit render layers, too
+    world_renderer.world.objects_rendered[1].state.render_layer = RenderLayer::Ui;
+    world_renderer.world.objects_rendered[0].state.render_layer = RenderLayer::Ui;
+    assert_eq!(at(&mut world_renderer, 25, 30), [255, 240, 40, 255]);
+    assert_eq!(at(&mut world_renderer, 35, 30), [255, 255, 255, 255]);
+}
+
+#[test]
+fn glowing_objects_in_the_world() {
+    // zoomed in, so the layer is drawn transformed
+    let mut world_renderer = scene(vec![WorldObject::new_abs(WorldObjectData::Rectangle { color: ORANGE }, 0, 0, 4, 4, 64, 64).in_world(-2.0, -2.0).with_emission(Emission::Color(WHITE))]);
+    world_renderer.world.camera.zoom = 2.0;
+    assert_eq!(at(&mut world_renderer, 32, 32), [240, 120, 20, 255]);
+    assert_eq!(at(&mut world_renderer, 40, 32), [0, 0, 0, 255]);
+}
+
+#[test]
+fn glowing_objects_can_light_up_the_world() {
+    let floor = common::backdrop(WHITE, SCREEN);
+    // a 4x4 lantern in the centre of the screen, which lights up 10 world units around it
+    let lamp = WorldObject::new_abs(WorldObjectData::Rectangle { color: ORANGE }, 0, 0, 4, 4, 64, 64).in_world(-2.0, -2.0).with_emission(Emission::Color(Pixel::opaque(255, 128, 0)));
+    let mut world_renderer = scene(vec![floor, lamp]);
+    assert_eq!(at(&mut world_renderer, 38, 32), [0, 0, 0, 255]);
+    world_renderer.world.objects_rendered[1].state.emitted_light = Some(10.0);
+    let lit = at(&mut world_renderer, 38, 32);
+    assert!(lit[0] > 50 && lit[0] > lit[1] && lit[2] == 0, "{:?}", lit);
+    assert_eq!(at(&mut world_renderer, 50, 32), [0, 0, 0, 255]);
+    // the light is only there while rendering
+    assert!(world_renderer.world.lights_rendered.is_empty());
+    let lights = world_renderer.world.emitted_lights();
+    assert_eq!(lights.len(), 1);
+    assert_eq!((lights[0].x, lights[0].y, lights[0].brightness, lights[0].range), (0.0, 0.0, (65535, 32896, 0), 10.0));
+}
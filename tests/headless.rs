@@ -0,0 +1,109 @@
+mod common;
+
+use common::WHITE;
+use rust_wasm_test_game::{renderer_headless, world::{render_world::{WorldRenderer, MAX_LIGHT, render_joiner::{JoinMode, ToneCurve, ToneMapping, multiply_factor}}, render_world_simd, render_world_layers::{ImageTransform, Pixel}, world::{World, Object::Objects::{DirectionalLight, LightFalloff, LightObject, OccluderObject, WorldObject, WorldObjectData}}}};
+
+// a white screen with one light in the middle of the world.
+fn lit_white_scene(width: usize, height: usize) -> WorldRenderer {
+    common::scene(32.0, (width, height), vec![common::backdrop(WHITE, (width, height))], vec![LightObject::new(0.0, 0.0, (50000, 30000, 10000), 8.0, 8.0)])
+}
+
+#[test]
+fn render_to_image_without_browser() {
+    let mut world_renderer = lit_white_scene(64, 48);
+    let (image, _) = renderer_headless::render_to_image(&mut world_renderer);
+    assert_eq!(image.dimensions(), (64, 48));
+    // inside the light's size, the full brightness is applied to the white rectangle
+    let center = image.get_pixel(32, 24).0;
+    assert_eq!(center, [multiply_factor(255, 50000) as u8, multiply_factor(255, 30000) as u8, multiply_factor(255, 10000) as u8, 255]);
+    // the corners are out of the light's range
+    assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(63, 47).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn render_to_png_roundtrip() {
+    let mut world_renderer = lit_white_scene(40, 30);
+    let path = std::env::temp_dir().join(format!("rust_wasm_test_game_headless_{}.png", std::process::id()));
+    renderer_headless::render_to_png(&mut world_renderer, &path).unwrap();
+    let loaded = image::open(&path).unwrap().to_rgba8();
+    std::fs::remove_file(&path).unwrap();
+    let (expected, _) = renderer_headless::render_to_image(&mut world_renderer);
+    assert_eq!(loaded, expected);
+}
+
+#[test]
+fn parallel_rendering_matches_single_threaded() {
+    for join_mode in [JoinMode::Nearest, JoinMode::Bilinear] {
+        let render = |parallel: bool| {
+            // sizes which don't divide evenly into bands of rows
+            let (width, height) = (203, 157);
+            let mut world_renderer = WorldRenderer::new(World::new(1600f32/9f32, 100f32), width, height);
+            let img = rust_wasm_test_game::assets::image_loader_hardcoded::get_image1_raw_bytes();
+            world_renderer.world.objects_rendered.push(WorldObject::new_rel(WorldObjectData::Rectangle { color: Pixel::opaque(40, 200, 90) }, 0.0, 0.0, 1.0, 1.0, width, height));
+            world_renderer.world.objects_rendered.push(WorldObject::new_abs(WorldObjectData::Image { rgba: img.0, width: img.1, height: img.2, transform: ImageTransform::default() }, 13, 7, 71, 133, width, height));
+            world_renderer.world.objects_rendered.push(WorldObject::new_rel(WorldObjectData::Rectangle { color: Pixel::from_straight_alpha(255, 0, 0, 128) }, 0.3, 0.45, 0.5, 0.3, width, height));
+            world_renderer.world.lights_rendered.push(LightObject::new(0.0, 0.0, (50000, 50000, 50000), 50.0, 50.0));
+            world_renderer.world.lights_rendered.push(LightObject::new(-60.0, -30.0, (0, 50000, 50000), 25.0, 50.0));
+            world_renderer.world.lights_rendered.push(LightObject::new(70.0, 60.0, (50000, 20000, 20000), 25.0, 75.0));
+            world_renderer.world.occluders.push(OccluderObject::Rectangle { x: -30.0, y: 10.0, w: 20.0, h: 10.0 });
+            world_renderer.join_mode = join_mode;
+            world_renderer.parallel = parallel;
+            world_renderer.init();
+            renderer_headless::render_to_image(&mut world_renderer).0
+        };
+        assert!(render(false) == render(true), "{:?}", join_mode);
+    }
+}
+
+#[test]
+fn simd_join_row_matches_multiply_factor() {
+    // every object value with a spread of light values, in an odd-length row so the last pixel takes the scalar path
+    let objects: Vec<Pixel> = (0..=255u8).map(|v| Pixel::opaque(v, 255 - v, v / 3)).chain(std::iter::once(Pixel::opaque(255, 255, 255))).collect();
+    for tone_mapping in [ToneMapping::Clamp, ToneMapping::Reinhard, ToneMapping::Aces] {
+        for linear in [false, true] {
+            let tone_curve = ToneCurve::new(tone_mapping, linear);
+            // also light brighter than full brightness, up to MAX_LIGHT
+            for light in (0..=MAX_LIGHT).step_by(257 * 13).chain([1, 255, 256, u16::MAX as u32 - 1, MAX_LIGHT]) {
+                let lights: Vec<(u32, u32, u32)> = objects.iter().enumerate().map(|(i, _)| (light, (light + i as u32 * 97) % (MAX_LIGHT + 1), MAX_LIGHT - light)).collect();
+                let mut buffer = vec![7u8; objects.len() * 4];
+                render_world_simd::join_row(&mut buffer, &objects, &lights, &tone_curve);
+                for (i, (obj, light)) in objects.iter().zip(lights.iter()).enumerate() {
+                    assert_eq!(buffer[i * 4..i * 4 + 4], [tone_curve.lit(obj.r, light.0), tone_curve.lit(obj.g, light.1), tone_curve.lit(obj.b, light.2), 7]);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn simd_rendering_matches_scalar() {
+    for (width, height, occluders) in [(203, 157, false), (203, 157, true), (64, 48, false), (35, 21, true)] {
+        let render = |simd: bool| {
+            let mut world_renderer = WorldRenderer::new(World::new(100.0, 80.0), width, height);
+            world_renderer.world.objects_rendered.push(WorldObject::new_rel(WorldObjectData::Rectangle { color: Pixel::opaque(255, 180, 33) }, 0.0, 0.0, 1.0, 1.0, width, height));
+            // lots of overlapping lights, some bright enough to saturate
+            for i in 0..40 {
+                let i = i as f32;
+                let light = LightObject::new((i * 37.0) % 200.0 - 100.0, (i * 53.0) % 160.0 - 80.0, (30000 + i as u16 * 500, 65535, 1000 * i as u16), i % 7.0 * 3.0, 10.0 + i);
+                // and some spotlights
+                let light = if i % 3.0 == 0.0 { light.with_cone(i, 0.2 + i % 4.0 * 0.2, 1.2) } else { light };
+                // and all kinds of falloffs
+                let falloff = [LightFalloff::Quadratic, LightFalloff::Linear, LightFalloff::Smoothstep, LightFalloff::InverseSquare { cutoff: 0.05 }, LightFalloff::Curve(vec![1.0, 0.7, 0.1, 0.0])][i as usize % 5].clone();
+                world_renderer.world.lights_rendered.push(light.with_falloff(falloff));
+            }
+            if occluders {
+                world_renderer.world.directional_lights.push(DirectionalLight::new(0.7, (3000, 2000, 1000), 30.0));
+                world_renderer.world.occluders.push(OccluderObject::Rectangle { x: -30.0, y: 10.0, w: 20.0, h: 10.0 });
+                world_renderer.world.occluders.push(OccluderObject::Line { x1: 20.0, y1: -50.0, x2: 60.0, y2: 0.0 });
+                world_renderer.world.ambient = (2000, 3000, 4000);
+                world_renderer.tone_mapping = ToneMapping::Aces;
+                world_renderer.linear_lighting = true;
+            }
+            world_renderer.simd = simd;
+            world_renderer.init();
+            renderer_headless::render_to_image(&mut world_renderer).0
+        };
+        assert!(render(false) == render(true), "{}x{}, occluders: {}", width, height, occluders);
+    }
+}
@@ -0,0 +1,121 @@
+use std::{f32::consts::PI, time::Duration};
+
+use rust_wasm_test_game::world::{render_world_layers::{ImageFilter, ImageTransform, Pixel}, world::Object::Objects::{WorldObject, WorldObjectData}};
+
+const RED: Pixel = Pixel::opaque(255, 0, 0);
+const GREEN: Pixel = Pixel::opaque(0, 255, 0);
+const BLUE: Pixel = Pixel::opaque(0, 0, 255);
+const WHITE: Pixel = Pixel::opaque(255, 255, 255);
+const BLACK: Pixel = Pixel::opaque(0, 0, 0);
+
+fn rgba(pixels: &[Pixel]) -> Vec<u8> {
+    pixels.iter().flat_map(|pixel| [pixel.r, pixel.g, pixel.b, pixel.a]).collect()
+}
+
+// the pixels of an image object with the given size, line by line
+fn draw(pixels: &[Pixel], width: usize, height: usize, layer_w: usize, layer_h: usize, transform: ImageTransform) -> Vec<Pixel> {
+    let mut object = WorldObject::new_abs(WorldObjectData::Image { rgba: rgba(pixels), width, height, transform }, 0, 0, layer_w, layer_h, 64, 64);
+    object.redraw();
+    object.state.layer.pixel_data.to_vec()
+}
+
+// red green
+// blue white
+fn corners(transform: ImageTransform) -> Vec<Pixel> {
+    draw(&[RED, GREEN, BLUE, WHITE], 2, 2, 2, 2, transform)
+}
+
+#[test]
+fn images_stay_where_they_are() {
+    let mut object = WorldObject::new_abs(WorldObjectData::Image { rgba: rgba(&[RED]), width: 1, height: 1, transform: ImageTransform::default() }, 10, 20, 4, 4, 64, 64);
+    object.redraw();
+    for millis in [0, 250, 999, 1500] {
+        (object.fns.draw_again)(&mut object.state, &Duration::from_millis(millis));
+        assert_eq!((object.state.layer.pos_x, object.state.layer.pos_y), (10.0, 20.0));
+    }
+}
+
+#[test]
+fn the_default_transform_changes_nothing() {
+    assert_eq!(corners(ImageTransform::default()), vec![RED, GREEN, BLUE, WHITE]);
+    // the same way as before there were transforms, even with filters that would have nothing to do
+    for filter in [ImageFilter::Bilinear, ImageFilter::Box] {
+        assert_eq!(corners(ImageTransform { filter, ..Default::default() }), vec![RED, GREEN, BLUE, WHITE]);
+    }
+}
+
+#[test]
+fn flipping() {
+    assert_eq!(corners(ImageTransform { flip_h: true, ..Default::default() }), vec![GREEN, RED, WHITE, BLUE]);
+    assert_eq!(corners(ImageTransform { flip_v: true, ..Default::default() }), vec![BLUE, WHITE, RED, GREEN]);
+    assert_eq!(corners(ImageTransform { flip_h: true, flip_v: true, ..Default::default() }), vec![WHITE, BLUE, GREEN, RED]);
+}
+
+#[test]
+fn rotation_is_clockwise() {
+    assert_eq!(corners(ImageTransform { rotation: PI / 2.0, ..Default::default() }), vec![BLUE, RED, WHITE, GREEN]);
+    assert_eq!(corners(ImageTransform { rotation: PI, ..Default::default() }), vec![WHITE, BLUE, GREEN, RED]);
+}
+
+#[test]
+fn corners_outside_of_the_layer_are_cut_off() {
+    let mut object = WorldObject::new_abs(WorldObjectData::Image { rgba: rgba(&[WHITE; 64]), width: 8, height: 8, transform: ImageTransform::default() }, 0, 0, 8, 8, 64, 64);
+    object.redraw();
+    assert!(object.state.layer.opaque);
+    if let WorldObjectData::Image { transform, .. } = &mut object.state.data {
+        transform.rotation = PI / 4.0;
+    }
+    object.redraw();
+    let layer = &object.state.layer;
+    assert!(!layer.opaque);
+    assert_eq!(layer.line(0)[0], Pixel::TRANSPARENT);
+    assert_eq!(layer.line(4)[4], WHITE);
+}
+
+#[test]
+fn scaling_around_the_centre() {
+    let pixels = draw(&[RED, GREEN, BLUE, WHITE], 2, 2, 4, 4, ImageTransform { scale: (0.5, 0.5), ..Default::default() });
+    let t = Pixel::TRANSPARENT;
+    assert_eq!(pixels, vec![t, t, t, t, t, RED, GREEN, t, t, BLUE, WHITE, t, t, t, t, t]);
+    // nothing to see at a scale of 0
+    assert!(draw(&[RED], 1, 1, 4, 4, ImageTransform { scale: (0.0, 1.0), ..Default::default() }).iter().all(|pixel| *pixel == t));
+}
+
+#[test]
+fn tint() {
+    let tint = Pixel::opaque(255, 0, 0);
+    assert_eq!(corners(ImageTransform { tint, ..Default::default() }), vec![RED, BLACK, BLACK, RED]);
+    let half = Pixel::from_straight_alpha(255, 255, 255, 128);
+    assert_eq!(corners(ImageTransform { tint: half, ..Default::default() })[0], Pixel { r: 128, g: 0, b: 0, a: 128 });
+}
+
+#[test]
+fn tinting_picks_the_same_pixels() {
+    // every pixel has its own colour, so picking another one shows
+    let image: Vec<Pixel> = (0..35).map(|i| Pixel::opaque(i * 7, 255 - i * 7, 100)).collect();
+    let tint = Pixel::opaque(255, 255, 254);
+    for (layer_w, layer_h) in [(7, 5), (3, 2), (6, 4), (10, 15), (14, 10), (5, 7), (1, 1)] {
+        let plain = draw(&image, 7, 5, layer_w, layer_h, ImageTransform::default());
+        let tinted = draw(&image, 7, 5, layer_w, layer_h, ImageTransform { tint, ..Default::default() });
+        assert_eq!(tinted, plain.iter().map(|pixel| pixel.tinted(tint)).collect::<Vec<_>>(), "{layer_w}x{layer_h}");
+    }
+}
+
+#[test]
+fn box_filter_averages_when_scaling_down() {
+    let checkerboard: Vec<Pixel> = (0..16).map(|i| if (i + i / 4) % 2 == 0 { BLACK } else { WHITE }).collect();
+    let scaled = |filter| draw(&checkerboard, 4, 4, 1, 1, ImageTransform { filter, ..Default::default() })[0];
+    assert_eq!(scaled(ImageFilter::Box), Pixel::opaque(128, 128, 128));
+    assert!([BLACK, WHITE].contains(&scaled(ImageFilter::Nearest)));
+}
+
+#[test]
+fn bilinear_filter_blends_when_scaling_up() {
+    let scaled = |filter| draw(&[BLACK, WHITE], 2, 1, 4, 1, ImageTransform { filter, ..Default::default() });
+    let grey = |v| Pixel::opaque(v, v, v);
+    assert_eq!(scaled(ImageFilter::Bilinear), vec![grey(0), grey(64), grey(191), grey(255)]);
+    assert_eq!(scaled(ImageFilter::Nearest), vec![BLACK, BLACK, WHITE, WHITE]);
+    // transparent pixels don't darken their neighbours
+    let pixels = draw(&[Pixel::TRANSPARENT, WHITE], 2, 1, 4, 1, ImageTransform { filter: ImageFilter::Bilinear, ..Default::default() });
+    assert_eq!(pixels[1], Pixel { r: 64, g: 64, b: 64, a: 64 });
+}
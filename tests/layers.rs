@@ -0,0 +1,341 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{FULL, SCREEN};
+use rust_wasm_test_game::{renderer_headless, world::{render_world::WorldRenderer, render_world_layers::{BlendMode, ImageTransform, Pixel}, world::{Background, RenderLayer, Object::Objects::{LightObject, WorldObject, WorldObjectData}}}};
+
+// a 4x2 image: red, green, blue, white on top, black below
+fn image() -> WorldObjectData {
+    let mut rgba = vec![];
+    for (r, g, b) in [(255, 0, 0), (0, 255, 0), (0, 0, 255), (255, 255, 255), (0, 0, 0), (0, 0, 0), (0, 0, 0), (0, 0, 0)] {
+        rgba.extend_from_slice(&[r, g, b, 255]);
+    }
+    WorldObjectData::Image { rgba, width: 4, height: 2, transform: ImageTransform::default() }
+}
+
+fn lit_scene(objects: Vec<WorldObject>) -> WorldRenderer {
+    common::scene(32.0, SCREEN, objects, vec![LightObject::new(0.0, 0.0, FULL, 64.0, 1.0)])
+}
+
+#[test]
+fn layers_only_store_their_own_pixels() {
+    let object = WorldObject::new_abs(WorldObjectData::Rectangle { color: Pixel::opaque(1, 2, 3) }, 10, 20, 8, 4, 1920, 1080);
+    assert_eq!(object.state.layer.pixel_data.len(), 8 * 4);
+    assert_eq!(object.state.layer.line(3).len(), 8);
+}
+
+#[test]
+fn shared_objects_use_the_same_pixels() {
+    let world_renderer = lit_scene(vec![
+        WorldObject::new_abs(image(), 0, 0, 8, 4, 64, 64),
+        WorldObject::new_shared(0, 20, 30, 64, 64),
+    ]);
+    let objects = &world_renderer.world.objects_rendered;
+    assert!(Arc::ptr_eq(&objects[0].state.layer.pixel_data, &objects[1].state.layer.pixel_data));
+    assert_eq!((objects[1].state.layer.pos_w, objects[1].state.layer.pos_h), (8, 4));
+}
+
+#[test]
+fn shared_objects_look_like_copies() {
+    let mut shared = lit_scene(vec![
+        WorldObject::new_abs(image(), 0, 0, 8, 4, 64, 64),
+        WorldObject::new_shared(0, 20, 30, 64, 64),
+    ]);
+    let mut copied = lit_scene(vec![
+        WorldObject::new_abs(image(), 0, 0, 8, 4, 64, 64),
+        WorldObject::new_abs(image(), 20, 30, 8, 4, 64, 64),
+    ]);
+    let shared = renderer_headless::render_to_image(&mut shared).0;
+    assert_eq!(shared, renderer_headless::render_to_image(&mut copied).0);
+    assert_eq!(shared.get_pixel(20 + 4, 30).0, [0, 0, 254, 255]); // blue, lit by a light just below full brightness
+}
+
+#[test]
+fn changing_a_shared_layer_copies_it() {
+    let mut world_renderer = lit_scene(vec![
+        WorldObject::new_abs(image(), 0, 0, 8, 4, 64, 64),
+        WorldObject::new_shared(0, 20, 30, 64, 64),
+    ]);
+    world_renderer.world.objects_rendered[1].state.layer.pixels_mut().fill(Pixel::TRANSPARENT);
+    let objects = &world_renderer.world.objects_rendered;
+    assert!(!Arc::ptr_eq(&objects[0].state.layer.pixel_data, &objects[1].state.layer.pixel_data));
+    assert_eq!(objects[0].state.layer.line(0)[0], Pixel::opaque(255, 0, 0));
+}
+
+#[test]
+fn shared_objects_follow_their_object() {
+    let mut world_renderer = lit_scene(vec![
+        WorldObject::new_abs(image(), 0, 0, 8, 4, 64, 64),
+        WorldObject::new_shared(0, 20, 30, 64, 64),
+    ]);
+    // the object is drawn green from now on
+    world_renderer.world.objects_rendered[0].fns.draw_again = Box::new(|state, _| state.layer.pixels_mut().fill(Pixel::opaque(0, 255, 0)));
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert_eq!(image.get_pixel(20, 30).0, [0, 254, 0, 255]);
+    assert_eq!(image.get_pixel(20 + 6, 30 + 3).0, [0, 254, 0, 255]);
+    // without copying the pixels, even though the object drew into them
+    let objects = &world_renderer.world.objects_rendered;
+    assert!(Arc::ptr_eq(&objects[0].state.layer.pixel_data, &objects[1].state.layer.pixel_data));
+    assert_eq!((objects[1].state.layer.pos_x, objects[1].state.layer.pos_y), (20.0, 30.0));
+}
+
+#[test]
+fn shared_objects_can_show_shared_objects() {
+    // the first object shows the second, which shows the image after it
+    let mut world_renderer = lit_scene(vec![
+        WorldObject::new_shared(1, 40, 10, 64, 64),
+        WorldObject::new_shared(2, 20, 30, 64, 64),
+        WorldObject::new_abs(image(), 0, 0, 8, 4, 64, 64),
+    ]);
+    world_renderer.world.objects_rendered[2].fns.draw_again = Box::new(|state, _| state.layer.pixels_mut().fill(Pixel::opaque(0, 255, 0)));
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert_eq!(image.get_pixel(40 + 7, 10 + 3).0, [0, 254, 0, 255]);
+    assert_eq!(image.get_pixel(20 + 7, 30 + 3).0, [0, 254, 0, 255]);
+    let objects = &world_renderer.world.objects_rendered;
+    assert!(Arc::ptr_eq(&objects[0].state.layer.pixel_data, &objects[2].state.layer.pixel_data));
+    assert!(Arc::ptr_eq(&objects[1].state.layer.pixel_data, &objects[2].state.layer.pixel_data));
+}
+
+#[test]
+fn shared_objects_without_pixels_to_show_are_empty() {
+    // past the end, and two that show each other
+    let mut world_renderer = lit_scene(vec![
+        WorldObject::new_shared(3, 20, 30, 64, 64),
+        WorldObject::new_shared(2, 0, 0, 64, 64),
+        WorldObject::new_shared(1, 0, 0, 64, 64),
+    ]);
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert!(image.pixels().all(|pixel| pixel.0 == [0, 0, 0, 255]));
+    assert!(world_renderer.world.objects_rendered.iter().all(|object| (object.state.layer.pos_w, object.state.layer.pos_h) == (0, 0)));
+}
+
+#[test]
+fn shared_objects_keep_their_own_blend_mode() {
+    let mut shared = WorldObject::new_shared(0, 20, 30, 64, 64);
+    shared.state.layer.blend_mode = BlendMode::Multiply;
+    let mut world_renderer = lit_scene(vec![WorldObject::new_abs(image(), 0, 0, 8, 4, 64, 64), shared]);
+    renderer_headless::render_to_image(&mut world_renderer);
+    let objects = &world_renderer.world.objects_rendered;
+    assert_eq!((objects[0].state.layer.blend_mode, objects[1].state.layer.blend_mode), (BlendMode::Normal, BlendMode::Multiply));
+    assert_eq!((objects[1].state.layer.pos_w, objects[1].state.layer.pos_h), (8, 4));
+}
+
+// where the red pixel (top left corner of image()) ends up with the layer at (x, y)
+fn red_pixels(x: f32, y: f32) -> Vec<(u32, u32)> {
+    let mut world_renderer = lit_scene(vec![WorldObject::new_abs(image(), 0, 0, 8, 4, 64, 64)]);
+    world_renderer.world.objects_rendered[0].state.layer.pos_x = x;
+    world_renderer.world.objects_rendered[0].state.layer.pos_y = y;
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    image.enumerate_pixels().filter(|(_, _, pixel)| pixel.0[0] > 0 && pixel.0[1] == 0).map(|(x, y, _)| (x, y)).collect()
+}
+
+#[test]
+fn layers_are_clipped_at_the_screen_edges() {
+    // the red part of image() is 2x2 pixels at this size
+    assert_eq!(red_pixels(10.0, 20.0), vec![(10, 20), (11, 20), (10, 21), (11, 21)]);
+    // partly off the left and top
+    assert_eq!(red_pixels(-1.0, -1.0), vec![(0, 0)]);
+    // partly off the right and bottom
+    assert_eq!(red_pixels(63.0, 63.0), vec![(63, 63)]);
+    // entirely off screen on every side
+    for (x, y) in [(-100.0, 0.0), (100.0, 0.0), (0.0, -100.0), (0.0, 100.0)] {
+        assert_eq!(red_pixels(x, y), vec![]);
+    }
+}
+
+#[test]
+fn layers_are_drawn_at_the_nearest_pixel() {
+    assert_eq!(red_pixels(10.4, 19.6), red_pixels(10.0, 20.0));
+    assert_eq!(red_pixels(-0.6, 62.6)[0], (0, 63));
+}
+
+#[test]
+fn moving_layers_leave_no_trail() {
+    let mut world_renderer = lit_scene(vec![WorldObject::new_abs(image(), 10, 20, 8, 4, 64, 64)]);
+    world_renderer.world.background = Background::Solid { color: (0, 0, 255) };
+    let before = renderer_headless::render_to_image(&mut world_renderer).0;
+    world_renderer.world.objects_rendered[0].state.layer.pos_x = 30.0;
+    let after = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert_eq!(after.get_pixel(10, 20), before.get_pixel(0, 0));
+    assert_eq!(after.get_pixel(30, 20), before.get_pixel(10, 20));
+    assert_eq!(red_pixels(30.0, 20.0), vec![(30, 20), (31, 20), (30, 21), (31, 21)]);
+}
+
+#[test]
+fn gradient_backgrounds_go_from_top_to_bottom() {
+    let opaque = |(r, g, b)| Pixel::opaque(r, g, b);
+    let background = Background::VerticalGradient { top: (0, 100, 200), bottom: (200, 100, 0) };
+    let mut line = vec![opaque((1, 2, 3)); 4];
+    background.draw_onto_line(&mut line, 0, 4, 0, 5);
+    assert_eq!(line, vec![opaque((0, 100, 200)); 4]);
+    background.draw_onto_line(&mut line, 1, 3, 2, 5);
+    assert_eq!(line, [(0, 100, 200), (100, 100, 100), (100, 100, 100), (0, 100, 200)].map(opaque));
+    background.draw_onto_line(&mut line, 0, 4, 4, 5);
+    assert_eq!(line, vec![opaque((200, 100, 0)); 4]);
+}
+
+#[test]
+fn image_backgrounds_are_tiled() {
+    let WorldObjectData::Image { rgba, width, height, .. } = image() else { unreachable!() };
+    let background = Background::Image { rgba, width, height };
+    let opaque = |(r, g, b)| Pixel::opaque(r, g, b);
+    let mut line = vec![opaque((1, 2, 3)); 10];
+    background.draw_onto_line(&mut line, 3, 10, 2, 64);
+    assert_eq!(line, [(1, 2, 3), (1, 2, 3), (1, 2, 3), (255, 255, 255), (255, 0, 0), (0, 255, 0), (0, 0, 255), (255, 255, 255), (255, 0, 0), (0, 255, 0)].map(opaque));
+}
+
+#[test]
+fn broken_image_backgrounds_are_black() {
+    let black = Pixel::opaque(0, 0, 0);
+    for background in [
+        Background::Image { rgba: vec![], width: 0, height: 0 },
+        Background::Image { rgba: vec![255; 4 * 4], width: 4, height: 0 },
+        Background::Image { rgba: vec![255; 4 * 3], width: 2, height: 2 },
+    ] {
+        let mut line = vec![Pixel::opaque(1, 2, 3); 10];
+        background.draw_onto_line(&mut line, 3, 10, 5, 64);
+        assert_eq!(line[2..4], [Pixel::opaque(1, 2, 3), black]);
+        assert!(line[3..].iter().all(|&pixel| pixel == black));
+    }
+}
+
+#[test]
+fn only_opaque_layers_hide_the_background() {
+    let mut world_renderer = lit_scene(vec![
+        WorldObject::new_abs(image(), -2, 60, 8, 4, 64, 64),
+        WorldObject::new_abs(WorldObjectData::Rectangle { color: Pixel::opaque(0, 0, 0) }, 0, 0, 8, 8, 64, 64),
+        WorldObject::new_abs(WorldObjectData::Rectangle { color: Pixel::from_straight_alpha(0, 0, 0, 128) }, 0, 0, 8, 8, 64, 64),
+    ]);
+    let layers: Vec<_> = world_renderer.world.objects_rendered.iter().map(|object| object.state.layer.opaque_area(64, 64)).collect();
+    assert_eq!(layers, vec![Some((0, 6, 60, 64)), Some((0, 8, 0, 8)), None]);
+    // drawing into a layer makes it count as not opaque, until whatever drew into it says otherwise
+    world_renderer.world.objects_rendered[1].state.layer.pixels_mut()[0] = Pixel::TRANSPARENT;
+    assert_eq!(world_renderer.world.objects_rendered[1].state.layer.opaque_area(64, 64), None);
+}
+
+#[test]
+fn pixels_are_premultiplied() {
+    assert_eq!(Pixel::from_straight_alpha(255, 100, 0, 128), Pixel { r: 128, g: 50, b: 0, a: 128 });
+    assert_eq!(Pixel::from_straight_alpha(255, 100, 0, 0), Pixel::TRANSPARENT);
+    assert_eq!(Pixel::from_straight_alpha(255, 100, 0, 255), Pixel::opaque(255, 100, 0));
+}
+
+#[test]
+fn blend_modes() {
+    let red = Pixel::from_straight_alpha(255, 0, 0, 128);
+    let grey = Pixel::opaque(100, 100, 200);
+    assert_eq!(red.blend_onto(grey, BlendMode::Normal), Pixel::opaque(178, 50, 100));
+    assert_eq!(Pixel::opaque(1, 2, 3).blend_onto(grey, BlendMode::Normal), Pixel::opaque(1, 2, 3));
+    assert_eq!(red.blend_onto(grey, BlendMode::Additive), Pixel::opaque(228, 100, 200));
+    assert_eq!(Pixel::opaque(200, 0, 0).blend_onto(grey, BlendMode::Additive), Pixel::opaque(255, 100, 200));
+    assert_eq!(Pixel::opaque(255, 128, 0).blend_onto(grey, BlendMode::Multiply), Pixel::opaque(100, 50, 0));
+    assert_eq!(red.blend_onto(grey, BlendMode::Multiply), Pixel::opaque(100, 50, 100));
+    assert_eq!(Pixel::opaque(255, 128, 0).blend_onto(grey, BlendMode::Screen), Pixel::opaque(255, 178, 200));
+    // nothing changes under a transparent pixel
+    for blend_mode in [BlendMode::Normal, BlendMode::Additive, BlendMode::Multiply, BlendMode::Screen] {
+        assert_eq!(Pixel::TRANSPARENT.blend_onto(grey, blend_mode), grey);
+        assert_eq!(Pixel::TRANSPARENT.blend_onto(red, blend_mode), red);
+    }
+}
+
+#[test]
+fn images_keep_their_alpha() {
+    let mut rgba = vec![];
+    for a in [0, 64, 128, 255] {
+        rgba.extend_from_slice(&[255, 255, 255, a]);
+    }
+    let mut world_renderer = lit_scene(vec![WorldObject::new_abs(WorldObjectData::Image { rgba, width: 4, height: 1, transform: ImageTransform::default() }, 0, 0, 4, 1, 64, 64)]);
+    world_renderer.world.background = Background::Solid { color: (0, 0, 200) };
+    let layer = &world_renderer.world.objects_rendered[0].state.layer;
+    assert_eq!(layer.line(0).iter().map(|pixel| pixel.a).collect::<Vec<_>>(), vec![0, 64, 128, 255]);
+    assert!(!layer.opaque);
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    let pixels: Vec<_> = (0..4).map(|x| image.get_pixel(x, 0).0).collect();
+    assert_eq!(pixels, vec![[0, 0, 199, 255], [63, 63, 213, 255], [127, 127, 227, 255], [254, 254, 254, 255]]);
+}
+
+#[test]
+fn layers_use_their_blend_mode() {
+    let mut world_renderer = lit_scene(vec![WorldObject::new_abs(WorldObjectData::Rectangle { color: Pixel::opaque(100, 0, 0) }, 0, 0, 4, 4, 64, 64)]);
+    world_renderer.world.background = Background::Solid { color: (100, 100, 100) };
+    world_renderer.world.objects_rendered[0].state.layer.blend_mode = BlendMode::Additive;
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert_eq!(image.get_pixel(0, 0).0, [199, 99, 99, 255]);
+    assert_eq!(image.get_pixel(4, 0).0, [99, 99, 99, 255]);
+    // an additive layer doesn't hide the background
+    assert_eq!(world_renderer.world.objects_rendered[0].state.layer.opaque_area(64, 64), None);
+}
+
+fn square(r: u8, g: u8, b: u8, x: isize) -> WorldObject {
+    WorldObject::new_abs(WorldObjectData::Rectangle { color: Pixel::opaque(r, g, b) }, x, 0, 8, 8, 64, 64)
+}
+
+#[test]
+fn objects_are_sorted_by_render_layer_and_z_index() {
+    let mut world_renderer = lit_scene(vec![
+        square(255, 0, 0, 0).on_render_layer(RenderLayer::Foreground, -5),
+        square(0, 255, 0, 0),
+        square(0, 0, 255, 4).on_render_layer(RenderLayer::World, 1),
+        square(255, 255, 255, 4),
+        square(0, 0, 0, 20).on_render_layer(RenderLayer::Background, 0),
+        square(1, 1, 1, 20),
+    ]);
+    world_renderer.world.set_lighting(RenderLayer::Ui, true);
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    // foreground over world, even with a smaller z_index
+    assert_eq!(image.get_pixel(2, 2).0, [254, 0, 0, 255]);
+    // bigger z_index on top
+    assert_eq!(image.get_pixel(10, 2).0, [0, 0, 254, 255]);
+    // the same z_index: in the order they were added
+    assert_eq!(image.get_pixel(22, 2).0, [0, 0, 0, 255]);
+}
+
+// a dark scene with a grey background, a white square in the world and a white one in the ui
+fn dark_scene() -> WorldRenderer {
+    let mut world_renderer = common::scene(32.0, SCREEN, vec![
+        square(255, 255, 255, 0),
+        WorldObject::new_abs(WorldObjectData::Rectangle { color: Pixel::from_straight_alpha(255, 255, 255, 128) }, 20, 0, 8, 8, 64, 64).on_render_layer(RenderLayer::Ui, 0),
+    ], vec![]);
+    world_renderer.world.background = Background::Solid { color: (100, 100, 100) };
+    world_renderer
+}
+
+#[test]
+fn ui_is_not_lit() {
+    let image = renderer_headless::render_to_image(&mut dark_scene()).0;
+    assert_eq!(image.get_pixel(2, 2).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(40, 2).0, [0, 0, 0, 255]);
+    // half of the ui pixel on top of the dark background
+    assert_eq!(image.get_pixel(22, 2).0, [128, 128, 128, 255]);
+}
+
+#[test]
+fn lighting_can_be_turned_off_per_render_layer() {
+    let mut world_renderer = dark_scene();
+    world_renderer.world.set_lighting(RenderLayer::Ui, true);
+    world_renderer.world.set_lighting(RenderLayer::Background, false);
+    assert!(!world_renderer.world.lighting(RenderLayer::Background));
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert_eq!(image.get_pixel(2, 2).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(40, 2).0, [100, 100, 100, 255]);
+    // the lit ui pixel is dark, what is left of the background below it isn't
+    assert_eq!(image.get_pixel(22, 2).0, [50, 50, 50, 255]);
+}
+
+#[test]
+fn split_lighting_matches_normal_blending_in_full_light() {
+    let render = |ui_lit: bool, blend_mode: BlendMode| {
+        let mut world_renderer = dark_scene();
+        world_renderer.world.lights_rendered.push(LightObject::new(0.0, 0.0, (u16::MAX, u16::MAX, u16::MAX), 64.0, 1.0));
+        world_renderer.world.set_lighting(RenderLayer::Ui, ui_lit);
+        world_renderer.world.objects_rendered[1].state.layer.blend_mode = blend_mode;
+        renderer_headless::render_to_image(&mut world_renderer).0
+    };
+    for blend_mode in [BlendMode::Normal, BlendMode::Additive, BlendMode::Multiply, BlendMode::Screen] {
+        for (lit, unlit) in render(true, blend_mode).pixels().zip(render(false, blend_mode).pixels()) {
+            // the lit parts are multiplied with a light just below full brightness
+            assert!(lit.0.iter().zip(unlit.0.iter()).all(|(lit, unlit)| lit.abs_diff(*unlit) <= 1), "{blend_mode:?}: {lit:?} {unlit:?}");
+        }
+    }
+}
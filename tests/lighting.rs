@@ -0,0 +1,258 @@
+use std::f32::consts::PI;
+
+mod common;
+
+use common::{SCREEN, WHITE};
+use rust_wasm_test_game::{renderer_headless, world::{render_world::{WorldRenderer, render_joiner::JoinMode}, world::Object::Objects::{DirectionalLight, LightCone, LightFalloff, LightObject, OccluderObject}}};
+
+// a white 64x64px screen showing a 64x64 world (-32..32 on both axes), lit by one light on the left.
+fn scene(occluders: Vec<OccluderObject>) -> WorldRenderer {
+    let mut world_renderer = common::scene(32.0, SCREEN, vec![common::backdrop(WHITE, SCREEN)], vec![LightObject::new(-16.0, 0.0, (60000, 60000, 60000), 4.0, 40.0)]);
+    world_renderer.world.occluders = occluders;
+    world_renderer
+}
+
+fn brightness_at(world_renderer: &mut WorldRenderer, x: u32, y: u32) -> u8 {
+    renderer_headless::render_to_image(world_renderer).0.get_pixel(x, y).0[0]
+}
+
+#[test]
+fn occluder_blocks_segment() {
+    let wall = OccluderObject::Rectangle { x: -4.0, y: -8.0, w: 8.0, h: 16.0 };
+    assert!(wall.blocks(-16.0, 0.0, 16.0, 0.0));
+    assert!(wall.blocks(0.0, 0.0, 0.0, 0.0)); // inside
+    assert!(!wall.blocks(-16.0, 0.0, 16.0, 24.0));
+    assert!(!wall.blocks(-16.0, 0.0, -8.0, 0.0)); // ends before the wall
+    let line = OccluderObject::Line { x1: 0.0, y1: -8.0, x2: 0.0, y2: 8.0 };
+    assert!(line.blocks(-16.0, 0.0, 16.0, 0.0));
+    assert!(!line.blocks(-16.0, 0.0, 16.0, 24.0));
+    assert!(!line.blocks(-16.0, -10.0, 16.0, -10.0)); // parallel to the wall's direction, but outside of it
+}
+
+#[test]
+fn walls_cast_hard_shadows() {
+    // without a wall, the cell right of the light is lit
+    assert!(brightness_at(&mut scene(vec![]), 47, 32) > 0);
+    for wall in [OccluderObject::Rectangle { x: -4.0, y: -8.0, w: 8.0, h: 16.0 }, OccluderObject::Line { x1: 0.0, y1: -8.0, x2: 0.0, y2: 8.0 }] {
+        let mut world_renderer = scene(vec![wall]);
+        // behind the wall: dark
+        assert_eq!(brightness_at(&mut world_renderer, 47, 32), 0);
+        // above the wall: lit
+        assert!(brightness_at(&mut world_renderer, 47 - 16, 32 - 12) > 0);
+        // further away, but with nothing in between: lit
+        assert!(brightness_at(&mut world_renderer, 47, 55) > 0);
+    }
+}
+
+// counts the pixels in column x that are neither fully lit nor fully shadowed by a wall covering the upper half of the world.
+fn penumbra_pixels(size: f32, x: u32) -> usize {
+    let column = |occluders: Vec<OccluderObject>| {
+        let mut world_renderer = scene(occluders);
+        world_renderer.world.lights_rendered[0].size = size;
+        let image = renderer_headless::render_to_image(&mut world_renderer).0;
+        (0..64).map(|y| image.get_pixel(x, y).0[0] as f32).collect::<Vec<_>>()
+    };
+    let unblocked = column(vec![]);
+    let blocked = column(vec![OccluderObject::Line { x1: 0.0, y1: -40.0, x2: 0.0, y2: 0.0 }]);
+    unblocked.iter().zip(blocked.iter()).filter(|(unblocked, blocked)| **unblocked > 0.0 && **blocked > 0.05 * **unblocked && **blocked < 0.95 * **unblocked).count()
+}
+
+#[test]
+fn light_size_sets_penumbra_width() {
+    // a (nearly) point-shaped light makes hard shadows
+    assert_eq!(penumbra_pixels(0.01, 47), 0);
+    // bigger lights make softer shadows
+    let small = penumbra_pixels(4.0, 47);
+    let big = penumbra_pixels(8.0, 47);
+    assert!(small > 0);
+    assert!(big > small, "penumbra of a big light ({}px) should be wider than that of a small one ({}px)", big, small);
+    // the penumbra gets wider further away from the occluder
+    assert!(penumbra_pixels(8.0, 54) > penumbra_pixels(8.0, 38));
+}
+
+// the red channel of row y, with a light map made of 8x8px cells.
+fn row_with_join_mode(join_mode: JoinMode, y: u32) -> Vec<u8> {
+    let mut world_renderer = scene(vec![]);
+    world_renderer.set_inaccuracy(8);
+    world_renderer.join_mode = join_mode;
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    (0..64).map(|x| image.get_pixel(x, y).0[0]).collect()
+}
+
+#[test]
+fn bilinear_join_removes_blocky_steps() {
+    let nearest = row_with_join_mode(JoinMode::Nearest, 20);
+    let bilinear = row_with_join_mode(JoinMode::Bilinear, 20);
+    let distinct = |row: &Vec<u8>| { let mut row = row.clone(); row.dedup(); row.len() };
+    // nearest: one value per 8px cell
+    assert!(distinct(&nearest) <= 8);
+    // bilinear: a smooth gradient between the cells
+    assert!(distinct(&bilinear) > 4 * distinct(&nearest), "{:?}", bilinear);
+    for pair in bilinear.windows(2) {
+        assert!((pair[0] as i32 - pair[1] as i32).abs() <= 8, "{:?}", bilinear);
+    }
+}
+
+#[test]
+fn bilinear_join_matches_nearest_in_uniform_light() {
+    let uniform = |join_mode| {
+        let mut world_renderer = scene(vec![]);
+        world_renderer.world.lights_rendered[0] = LightObject::new(0.0, 0.0, (40000, 20000, 10000), 100.0, 1.0);
+        world_renderer.set_inaccuracy(4);
+        world_renderer.join_mode = join_mode;
+        renderer_headless::render_to_image(&mut world_renderer).0
+    };
+    assert_eq!(uniform(JoinMode::Nearest), uniform(JoinMode::Bilinear));
+}
+
+#[test]
+fn lights_are_binned_by_their_reach() {
+    let mut world_renderer = scene(vec![]);
+    // 64 small torches in a grid, plus lights just outside each edge of the screen that only reach a few pixels into it
+    world_renderer.world.lights_rendered.clear();
+    for i in 0..64 {
+        world_renderer.world.lights_rendered.push(LightObject::new((i % 8) as f32 * 8.0 - 28.0, (i / 8) as f32 * 8.0 - 28.0, (20000, 20000, 20000), 0.5, 1.5));
+    }
+    for (x, y) in [(-36.0, 0.0), (36.0, 0.0), (0.0, -36.0), (0.0, 36.0)] {
+        world_renderer.world.lights_rendered.push(LightObject::new(x, y, (60000, 60000, 60000), 1.0, 7.0));
+    }
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    for (x, y) in [(0, 32), (63, 32), (32, 0), (32, 63), (12, 12), (42, 58)] {
+        assert!(image.get_pixel(x, y).0[0] > 0, "({}, {}) should be lit", x, y);
+    }
+    for (x, y) in [(0, 0), (63, 63), (8, 8)] {
+        assert_eq!(image.get_pixel(x, y).0[0], 0, "({}, {}) should be dark", x, y);
+    }
+    // the order of the lights doesn't matter
+    world_renderer.world.lights_rendered.reverse();
+    assert_eq!(renderer_headless::render_to_image(&mut world_renderer).0, image);
+}
+
+#[test]
+fn tiles_only_list_the_lights_that_reach_them() {
+    // the light map has 32x32 cells, so 2x2 tiles of 32x32 pixels each
+    let mut world_renderer = scene(vec![]);
+    world_renderer.world.lights_rendered.clear();
+    // in the top left tile only, and just outside of the right edge of the screen, reaching into both right tiles
+    world_renderer.world.lights_rendered.push(LightObject::new(-16.0, -16.0, (60000, 60000, 60000), 1.0, 3.0));
+    world_renderer.world.lights_rendered.push(LightObject::new(36.0, 0.0, (60000, 60000, 60000), 1.0, 7.0));
+    renderer_headless::render_to_image(&mut world_renderer);
+    let tiles = &world_renderer.lights_renderer;
+    assert_eq!(tiles.lights_in_tile_at(8, 8), [0]);
+    assert_eq!(tiles.lights_in_tile_at(48, 8), [1]);
+    assert_eq!(tiles.lights_in_tile_at(48, 48), [1]);
+    assert!(tiles.lights_in_tile_at(8, 48).is_empty());
+}
+
+#[test]
+fn light_cone_factor() {
+    let cone = LightCone { direction: 0.0, inner_angle: 0.3, outer_angle: 0.6 };
+    assert_eq!(cone.factor(1.0, 0.0), 1.0);
+    assert_eq!(cone.factor(5.0, 1.0), 1.0); // inside of the inner angle
+    assert_eq!(cone.factor(0.0, 1.0), 0.0);
+    assert_eq!(cone.factor(-1.0, 0.0), 0.0);
+    let edge = cone.factor(0.45f32.cos(), 0.45f32.sin());
+    assert!(edge > 0.0 && edge < 1.0, "{edge}");
+    // the light itself is always lit
+    assert_eq!(cone.factor(0.0, 0.0), 1.0);
+    // clockwise, so a quarter turn points down (towards +y)
+    let down = LightCone { direction: PI / 2.0, ..cone };
+    assert_eq!(down.factor(0.0, 1.0), 1.0);
+    assert_eq!(down.factor(1.0, 0.0), 0.0);
+}
+
+#[test]
+fn spotlights_shine_in_one_direction() {
+    let mut world_renderer = scene(vec![]);
+    // all around the light (at pixel 16, 32)
+    for (x, y) in [(28, 32), (4, 32), (16, 20), (16, 44)] {
+        assert!(brightness_at(&mut world_renderer, x, y) > 0);
+    }
+    world_renderer.world.lights_rendered[0] = LightObject::new(-16.0, 0.0, (60000, 60000, 60000), 4.0, 40.0).with_cone(0.0, 0.3, 0.5);
+    assert!(brightness_at(&mut world_renderer, 28, 32) > 0);
+    for (x, y) in [(4, 32), (16, 20), (16, 44)] {
+        assert_eq!(brightness_at(&mut world_renderer, x, y), 0, "({x}, {y})");
+    }
+    // the edge of the cone fades out
+    let right = brightness_at(&mut world_renderer, 40, 32);
+    let edge = brightness_at(&mut world_renderer, 40, 32 + 12);
+    assert!(edge > 0 && edge < right, "{edge} {right}");
+    // turned to point down
+    world_renderer.world.lights_rendered[0].cone.as_mut().unwrap().direction = PI / 2.0;
+    assert!(brightness_at(&mut world_renderer, 16, 44) > 0);
+    assert_eq!(brightness_at(&mut world_renderer, 28, 32), 0);
+}
+
+#[test]
+fn directional_lights_reach_everything_and_cast_shadows() {
+    let mut world_renderer = scene(vec![]);
+    world_renderer.world.lights_rendered.clear();
+    world_renderer.world.directional_lights.push(DirectionalLight::new(0.0, (30000, 30000, 30000), 20.0));
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    let expected = image.get_pixel(0, 0).0;
+    assert!(expected[0] > 100);
+    assert!(image.pixels().all(|pixel| pixel.0 == expected));
+    // the light goes towards +x, so a wall in the middle has a shadow on its right which is 20 world units long
+    world_renderer.world.occluders.push(OccluderObject::Rectangle { x: -4.0, y: -8.0, w: 8.0, h: 16.0 });
+    assert_eq!(brightness_at(&mut world_renderer, 42, 32), 0);
+    assert_eq!(brightness_at(&mut world_renderer, 20, 32), expected[0]);
+    assert_eq!(brightness_at(&mut world_renderer, 60, 32), expected[0]);
+    assert_eq!(brightness_at(&mut world_renderer, 42, 50), expected[0]);
+    // directional lights add up with point lights
+    world_renderer.world.lights_rendered.push(LightObject::new(-16.0, 0.0, (20000, 20000, 20000), 4.0, 10.0));
+    assert!(brightness_at(&mut world_renderer, 16, 32) > expected[0]);
+}
+
+#[test]
+fn light_falloff_factor() {
+    // a light with a size of 2 that reaches 10, so t is (distance - 2) / 8
+    let factor = |falloff: &LightFalloff, distance: f32| falloff.factor(distance * distance, 2.0, 10.0);
+    let all = [LightFalloff::Quadratic, LightFalloff::Linear, LightFalloff::Smoothstep, LightFalloff::InverseSquare { cutoff: 0.01 }, LightFalloff::Curve(vec![1.0, 0.2, 0.0])];
+    for falloff in all.iter() {
+        assert!((factor(falloff, 2.0) - 1.0).abs() < 1e-6, "{:?}", falloff);
+        assert!(factor(falloff, 10.0).abs() < 1e-6, "{:?}", falloff);
+        let steps: Vec<f32> = (20..=100).map(|distance| factor(falloff, distance as f32 / 10.0)).collect();
+        assert!(steps.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", falloff);
+    }
+    // the default is the falloff lights always had
+    assert!((factor(&LightFalloff::Quadratic, 6.0) - ((1.0 - 36.0 / 100.0) / (1.0 - 4.0 / 100.0f32)).powi(2)).abs() < 1e-6);
+    assert_eq!(LightObject::new(0.0, 0.0, (1, 1, 1), 1.0, 1.0).falloff, LightFalloff::Quadratic);
+    assert_eq!(factor(&LightFalloff::Linear, 6.0), 0.5);
+    assert_eq!(factor(&LightFalloff::Linear, 4.0), 0.75);
+    assert_eq!(factor(&LightFalloff::Smoothstep, 6.0), 0.5);
+    // soft at both ends
+    assert!(factor(&LightFalloff::Smoothstep, 3.0) > factor(&LightFalloff::Linear, 3.0));
+    assert!(factor(&LightFalloff::Smoothstep, 9.0) < factor(&LightFalloff::Linear, 9.0));
+    // inverse square drops quickly close to the light and then slowly
+    let inverse_square = LightFalloff::InverseSquare { cutoff: 0.01 };
+    assert!(factor(&inverse_square, 4.0) < 0.2 && factor(&inverse_square, 8.0) > 0.0);
+    // cutoffs at (or past) the ends still go from 1 to 0
+    for cutoff in [0.0, 1.0, -1.0, 2.0] {
+        let inverse_square = LightFalloff::InverseSquare { cutoff };
+        let steps: Vec<f32> = (20..=100).map(|distance| factor(&inverse_square, distance as f32 / 10.0)).collect();
+        assert!(steps.iter().all(|step| (0.0..=1.0).contains(step)), "{}: {:?}", cutoff, steps);
+        assert!(steps.windows(2).all(|pair| pair[0] >= pair[1]), "{}: {:?}", cutoff, steps);
+        assert!((steps[0] - 1.0).abs() < 1e-6 && steps[80].abs() < 1e-6, "{}: {:?}", cutoff, steps);
+    }
+    // curves are interpolated between their points
+    let curve = LightFalloff::Curve(vec![1.0, 0.2, 0.0]);
+    assert!((factor(&curve, 4.0) - 0.6).abs() < 1e-6);
+    assert!((factor(&curve, 6.0) - 0.2).abs() < 1e-6);
+    assert_eq!(factor(&LightFalloff::Curve(vec![]), 4.0), 0.0);
+}
+
+#[test]
+fn lights_use_their_falloff() {
+    let mut world_renderer = scene(vec![]);
+    // the light is at pixel 16 with a size of 4 and reaches 44 world units, so pixel 40 is at about t = 0.5
+    let quadratic = brightness_at(&mut world_renderer, 40, 32);
+    let light = &mut world_renderer.world.lights_rendered[0];
+    *light = LightObject::new(light.x, light.y, light.brightness, light.size, light.range).with_falloff(LightFalloff::Linear);
+    let linear = brightness_at(&mut world_renderer, 40, 32);
+    // about half of 60000 on white (the light map cells are a bit further away)
+    assert!((100..=117).contains(&linear), "{linear}");
+    assert!(linear != quadratic);
+    world_renderer.world.lights_rendered[0].falloff = LightFalloff::Curve(vec![1.0, 1.0, 1.0]);
+    // a flat curve is full brightness right up to the end of the range
+    assert_eq!(brightness_at(&mut world_renderer, 40, 32), brightness_at(&mut world_renderer, 16, 32));
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::{FULL, SCREEN, WHITE};
+use rust_wasm_test_game::{renderer_headless, world::{render_world::{WorldRenderer, render_joiner::JoinMode}, render_world_layers::Pixel, world::Object::Objects::{LightObject, WorldObject, WorldObjectData}}};
+
+const RED: Pixel = Pixel::opaque(255, 0, 0);
+
+// a 64x64 screen showing a world of -31.5..31.5 (one world unit per pixel), brightly lit everywhere
+fn scene(objects: Vec<WorldObject>) -> WorldRenderer {
+    common::scene(31.5, SCREEN, objects, vec![LightObject::new(0.0, 0.0, FULL, 200.0, 1.0)])
+}
+
+#[test]
+fn the_picture_has_the_new_size() {
+    let mut world_renderer = scene(vec![common::backdrop(WHITE, SCREEN)]);
+    world_renderer.resize(128, 96);
+    assert_eq!((world_renderer.width, world_renderer.height), (128, 96));
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert_eq!(image.dimensions(), (128, 96));
+    // the rectangle still fills the whole screen, and the light map covers all of it
+    assert!(image.pixels().all(|pixel| pixel.0[0] > 250), "{:?}", image.get_pixel(127, 95));
+    // smaller again
+    world_renderer.resize(10, 20);
+    assert_eq!(renderer_headless::render_to_image(&mut world_renderer).0.dimensions(), (10, 20));
+}
+
+#[test]
+fn objects_keep_their_place() {
+    let mut world_renderer = scene(vec![
+        WorldObject::new_rel(WorldObjectData::Rectangle { color: RED }, 0.25, 0.25, 0.5, 0.5, 64, 64),
+        WorldObject::new_abs(WorldObjectData::Rectangle { color: RED }, 3, 4, 5, 6, 64, 64),
+    ]);
+    world_renderer.resize(128, 96);
+    let relative = &world_renderer.world.objects_rendered[0].state;
+    assert_eq!((relative.layer.pos_x, relative.layer.pos_y, relative.layer.pos_w, relative.layer.pos_h), (32.0, 24.0, 64, 48));
+    assert_eq!((relative.width, relative.height, relative.layer.width, relative.layer.height), (128, 96, 128, 96));
+    // drawn again at the new size
+    assert!(relative.layer.pixel_data.iter().all(|pixel| *pixel == RED));
+    assert!(relative.layer.opaque);
+    let absolute = &world_renderer.world.objects_rendered[1].state.layer;
+    assert_eq!((absolute.pos_x, absolute.pos_y, absolute.pos_w, absolute.pos_h), (3.0, 4.0, 5, 6));
+}
+
+#[test]
+fn the_world_is_not_stretched() {
+    let mut world_renderer = scene(vec![WorldObject::new_abs(WorldObjectData::Rectangle { color: RED }, 0, 0, 4, 4, 64, 64).in_world(-2.0, -2.0)]);
+    // twice as wide: the world keeps its height and gets wider
+    world_renderer.resize(127, 64);
+    assert_eq!((world_renderer.world.width, world_renderer.world.height), (63.0, 31.5));
+    let layer = &world_renderer.world.objects_rendered[0].state.layer;
+    assert_eq!(layer.zoom, 1.0);
+    // twice as many pixels in both directions, like on a screen with a devicePixelRatio of 2: objects in the world get twice as big
+    world_renderer.resize(253, 127);
+    assert_eq!((world_renderer.world.width, world_renderer.world.height), (63.0, 31.5));
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    let layer = &world_renderer.world.objects_rendered[0].state.layer;
+    assert_eq!((layer.pos_x, layer.pos_y, layer.zoom), (122.0, 59.0, 2.0));
+    let red_pixels = image.pixels().filter(|pixel| pixel.0[0] > 200 && pixel.0[1] < 50).count();
+    assert_eq!(red_pixels, 8 * 8);
+    // the light is still in the centre
+    assert!(image.get_pixel(126, 63).0[0] > 250);
+}
+
+#[test]
+fn shared_objects_are_linked_again() {
+    let mut world_renderer = scene(vec![
+        WorldObject::new_rel(WorldObjectData::Rectangle { color: RED }, 0.0, 0.0, 0.25, 0.25, 64, 64),
+        WorldObject::new_shared(0, 40, 40, 64, 64),
+    ]);
+    world_renderer.resize(128, 128);
+    let shared = &world_renderer.world.objects_rendered[1].state.layer;
+    assert_eq!((shared.pos_x, shared.pos_y, shared.pos_w, shared.pos_h), (40.0, 40.0, 32, 32));
+    assert!(std::sync::Arc::ptr_eq(&shared.pixel_data, &world_renderer.world.objects_rendered[0].state.layer.pixel_data));
+}
+
+#[test]
+fn resizing_to_the_same_size_changes_nothing() {
+    let mut world_renderer = scene(vec![WorldObject::new_rel(WorldObjectData::Rectangle { color: RED }, 0.25, 0.25, 0.5, 0.5, 64, 64)]);
+    let before = renderer_headless::render_to_image(&mut world_renderer).0;
+    world_renderer.resize(64, 64);
+    assert_eq!(world_renderer.world.width, 31.5);
+    assert_eq!(renderer_headless::render_to_image(&mut world_renderer).0, before);
+}
+
+#[test]
+fn tiny_and_empty_sizes() {
+    // a hidden or collapsed page can report a size of 0
+    let mut world_renderer = scene(vec![WorldObject::new_rel(WorldObjectData::Rectangle { color: RED }, 0.25, 0.25, 0.5, 0.5, 64, 64)]);
+    for (width, height) in [(1, 1), (0, 0), (0, 10), (10, 0), (1, 7), (3, 2), (64, 64)] {
+        world_renderer.resize(width, height);
+        for join_mode in [JoinMode::Nearest, JoinMode::Bilinear] {
+            world_renderer.join_mode = join_mode;
+            let image = renderer_headless::render_to_image(&mut world_renderer).0;
+            assert_eq!(image.dimensions(), (width as u32, height as u32));
+        }
+    }
+    // the world isn't squashed on the way
+    assert_eq!((world_renderer.world.width, world_renderer.world.height), (31.5, 31.5));
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert!(image.get_pixel(32, 32).0[0] > 250);
+}
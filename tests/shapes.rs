@@ -0,0 +1,116 @@
+use rust_wasm_test_game::world::{render_world_layers::Pixel, shapes::Shape, world::Object::Objects::{WorldObject, WorldObjectData}};
+
+const RED: Pixel = Pixel::opaque(255, 0, 0);
+const BLUE: Pixel = Pixel::opaque(0, 0, 255);
+const T: Pixel = Pixel::TRANSPARENT;
+
+// a 20x20 shapes object
+fn draw(shapes: Vec<(Shape, Pixel)>) -> WorldObject {
+    let mut object = WorldObject::new_abs(WorldObjectData::Shapes { shapes }, 0, 0, 20, 20, 64, 64);
+    object.redraw();
+    object
+}
+
+fn pixel(object: &WorldObject, x: usize, y: usize) -> Pixel {
+    object.state.layer.line(y)[x]
+}
+
+fn partly_covered(pixel: Pixel) -> bool {
+    pixel.a > 0 && pixel.a < 255
+}
+
+// pixels right at the edge are only almost fully covered
+fn covered(pixel: Pixel) -> bool {
+    pixel.a >= 240
+}
+
+#[test]
+fn circles_are_anti_aliased() {
+    let object = draw(vec![(Shape::Circle { x: 10.0, y: 10.0, radius: 6.0, outline: None }, RED)]);
+    assert_eq!(pixel(&object, 10, 10), RED);
+    assert_eq!(pixel(&object, 0, 0), T);
+    assert!(!object.state.layer.opaque);
+    // the edge at 45 degrees goes through pixels, which are only partly red
+    assert!(partly_covered(pixel(&object, 14, 14)), "{:?}", pixel(&object, 14, 14));
+    // straight up, the edge is between two lines of pixels
+    assert!(covered(pixel(&object, 10, 4)));
+    assert_eq!(pixel(&object, 10, 3), T);
+    // premultiplied, so partly covered pixels are still pure red
+    assert!(object.state.layer.pixel_data.iter().all(|pixel| pixel.g == 0 && pixel.r == pixel.a));
+}
+
+#[test]
+fn outlines_leave_the_inside_empty() {
+    let object = draw(vec![(Shape::Circle { x: 10.0, y: 10.0, radius: 6.0, outline: Some(2.0) }, RED)]);
+    assert_eq!(pixel(&object, 10, 10), T);
+    assert!(covered(pixel(&object, 10, 4)) && covered(pixel(&object, 10, 3)));
+    assert_eq!(pixel(&object, 10, 2), T);
+    let object = draw(vec![(Shape::Ellipse { x: 10.0, y: 10.0, radius_x: 8.0, radius_y: 4.0, outline: Some(2.0) }, RED)]);
+    assert_eq!(pixel(&object, 10, 10), T);
+    assert!(covered(pixel(&object, 2, 10)) && covered(pixel(&object, 10, 6)));
+    assert_eq!(pixel(&object, 10, 4), T);
+}
+
+#[test]
+fn thick_lines_and_polylines() {
+    let object = draw(vec![(Shape::Line { x1: 2.0, y1: 10.0, x2: 18.0, y2: 10.0, thickness: 4.0 }, RED)]);
+    assert_eq!((7..13).map(|y| pixel(&object, 10, y)).collect::<Vec<_>>(), [T, RED, RED, RED, RED, T]);
+    // the ends are round
+    assert!(covered(pixel(&object, 1, 10)));
+    assert!(partly_covered(pixel(&object, 0, 8)));
+    // diagonal lines are smooth
+    let object = draw(vec![(Shape::Line { x1: 0.0, y1: 0.0, x2: 20.0, y2: 13.0, thickness: 1.0 }, RED)]);
+    assert!(object.state.layer.pixel_data.iter().any(|pixel| partly_covered(*pixel)));
+    // an open polyline doesn't connect its ends, a closed one does
+    let points = vec![(2.5, 2.5), (17.5, 2.5), (17.5, 17.5)];
+    let open = draw(vec![(Shape::Polyline { points: points.clone(), thickness: 1.0, closed: false }, RED)]);
+    let closed = draw(vec![(Shape::Polyline { points, thickness: 1.0, closed: true }, RED)]);
+    assert_eq!((pixel(&open, 10, 2), pixel(&open, 17, 10), pixel(&open, 10, 10)), (RED, RED, T));
+    assert_eq!(pixel(&closed, 10, 10), RED);
+    assert_eq!(pixel(&closed, 5, 10), T);
+}
+
+#[test]
+fn polygons() {
+    let triangle = vec![(2.0, 2.0), (18.0, 2.0), (2.0, 18.0)];
+    let filled = draw(vec![(Shape::Polygon { points: triangle.clone(), outline: None }, RED)]);
+    assert_eq!((pixel(&filled, 5, 5), pixel(&filled, 15, 15)), (RED, T));
+    assert!(partly_covered(pixel(&filled, 10, 9)));
+    let outlined = draw(vec![(Shape::Polygon { points: triangle, outline: Some(2.0) }, RED)]);
+    assert_eq!((pixel(&outlined, 5, 5), pixel(&outlined, 10, 2), pixel(&outlined, 2, 10)), (T, RED, RED));
+    // self-intersecting polygons use the even-odd rule
+    let star = (0..5).map(|i| {
+        let angle = i as f32 * 4.0 * std::f32::consts::PI / 5.0;
+        (10.0 + 9.0 * angle.sin(), 10.0 - 9.0 * angle.cos())
+    }).collect();
+    let star = draw(vec![(Shape::Polygon { points: star, outline: None }, RED)]);
+    assert_eq!((pixel(&star, 10, 10), pixel(&star, 10, 5)), (T, RED));
+}
+
+#[test]
+fn rounded_rectangles() {
+    let object = draw(vec![(Shape::RoundedRect { x: 2.0, y: 2.0, w: 16.0, h: 12.0, radius: 4.0, outline: None }, RED)]);
+    assert_eq!((pixel(&object, 2, 2), pixel(&object, 10, 2), pixel(&object, 2, 8), pixel(&object, 10, 8)), (T, RED, RED, RED));
+    assert_eq!((pixel(&object, 1, 8), pixel(&object, 10, 14)), (T, T));
+    assert!(partly_covered(pixel(&object, 3, 3)));
+    // radius 0 is a plain rectangle
+    let object = draw(vec![(Shape::RoundedRect { x: 2.0, y: 2.0, w: 16.0, h: 12.0, radius: 0.0, outline: None }, RED)]);
+    assert_eq!((pixel(&object, 2, 2), pixel(&object, 17, 13), pixel(&object, 18, 13)), (RED, RED, T));
+}
+
+#[test]
+fn shapes_are_drawn_in_order() {
+    let mut object = draw(vec![
+        (Shape::Circle { x: 10.0, y: 10.0, radius: 8.0, outline: None }, RED),
+        (Shape::Circle { x: 10.0, y: 10.0, radius: 2.0, outline: None }, BLUE),
+        // far outside of the layer
+        (Shape::Line { x1: -100.0, y1: -100.0, x2: -50.0, y2: 200.0, thickness: 3.0 }, BLUE),
+    ]);
+    assert_eq!((pixel(&object, 10, 10), pixel(&object, 10, 5)), (BLUE, RED));
+    if let WorldObjectData::Shapes { shapes } = &mut object.state.data {
+        shapes.pop();
+        shapes.pop();
+    }
+    object.redraw();
+    assert_eq!(pixel(&object, 10, 10), RED);
+}
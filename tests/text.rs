@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use rust_wasm_test_game::world::{render_world_layers::Pixel, text::{Font, Text, TextAlign}, world::Object::Objects::{WorldObject, WorldObjectData}};
+
+const RED: Pixel = Pixel::opaque(255, 0, 0);
+const T: Pixel = Pixel::TRANSPARENT;
+
+// 2x3 glyphs: 'A' is all white, 'B' only in its left column, ' ' is empty
+fn bitmap_font() -> Arc<Font> {
+    let (w, x) = ([255, 255, 255, 255], [0, 0, 0, 0]);
+    let mut rgba = vec![];
+    for _ in 0..3 {
+        for pixel in [w, w, w, x, x, x] {
+            rgba.extend_from_slice(&pixel);
+        }
+    }
+    Arc::new(Font::bitmap(&rgba, 6, 3, 2, 3, "AB "))
+}
+
+fn text_object(text: Text, width: usize, height: usize) -> WorldObject {
+    let mut object = WorldObject::new_abs(WorldObjectData::Text { text }, 0, 0, width, height, 64, 64);
+    object.redraw();
+    object
+}
+
+#[test]
+fn wrapping_and_measuring() {
+    let text = Text::new("AB AB\nA AAAAA", bitmap_font(), RED);
+    assert_eq!(text.lines(None), vec!["AB AB", "A AAAAA"]);
+    assert_eq!(text.measure(None), (14, 6));
+    assert_eq!(text.lines(Some(8.0)), vec!["AB", "AB", "A", "AAAA", "A"]);
+    assert_eq!(text.measure(Some(8.0)), (8, 15));
+    // a single character that is wider than a line still gets a line
+    assert_eq!(text.lines(Some(1.0)).len(), 10);
+}
+
+#[test]
+fn bitmap_text_is_tinted() {
+    let object = text_object(Text::new("AB", bitmap_font(), RED), 5, 3);
+    for y in 0..3 {
+        assert_eq!(object.state.layer.line(y), [RED, RED, RED, T, T]);
+    }
+    assert!(!object.state.layer.opaque);
+}
+
+#[test]
+fn alignment() {
+    let line = |align| {
+        let mut text = Text::new("B", bitmap_font(), RED);
+        text.align = align;
+        text_object(text, 6, 3).state.layer.line(0).to_vec()
+    };
+    assert_eq!(line(TextAlign::Left), [RED, T, T, T, T, T]);
+    assert_eq!(line(TextAlign::Center), [T, T, RED, T, T, T]);
+    assert_eq!(line(TextAlign::Right), [T, T, T, T, RED, T]);
+}
+
+#[test]
+fn wrapped_text_uses_the_width_of_the_layer() {
+    let mut text = Text::new("B B", bitmap_font(), RED);
+    text.wrap = true;
+    let object = text_object(text, 4, 6);
+    assert_eq!(object.state.layer.line(0), [RED, T, T, T]);
+    assert_eq!(object.state.layer.line(3), [RED, T, T, T]);
+}
+
+#[test]
+fn changed_text_is_drawn_in_the_next_frame() {
+    let mut object = text_object(Text::new("B", bitmap_font(), RED), 4, 3);
+    object.text().unwrap().set("AA");
+    assert_eq!(object.text().unwrap().text(), "AA");
+    (object.fns.draw_again)(&mut object.state, &std::time::Duration::ZERO);
+    assert_eq!(object.state.layer.line(0), [RED; 4]);
+}
+
+#[test]
+fn true_type_text() {
+    // Noto Sans (Apache License 2.0, see fixtures/NotoSans-LICENSE.txt)
+    let bytes = include_bytes!("fixtures/NotoSans-Regular.ttf");
+    let font = Arc::new(Font::true_type(bytes, 20.0).unwrap());
+    assert!(Font::true_type(&bytes[..100], 20.0).is_err());
+    let text = Text::new("Hi\nthere", font.clone(), RED);
+    let (width, height) = text.measure(None);
+    assert!((40..60).contains(&width), "{width}");
+    assert_eq!(height, (font.line_height() * 2.0).ceil() as usize);
+    let object = text_object(text, width, height);
+    let pixels = &object.state.layer.pixel_data;
+    // anti-aliased red: some pixels are fully covered, some only partly, and all of them are red
+    assert!(pixels.iter().any(|pixel| pixel.a > 0 && pixel.a < 255));
+    assert!(pixels.iter().all(|pixel| pixel.g == 0 && pixel.r == pixel.a));
+    let covered = |x: usize, y: usize| object.state.layer.line(y)[x].a > 200;
+    let empty = |x: usize, y: usize| object.state.layer.line(y)[x].a == 0;
+    // the 'H': two stems from the cap height down to the baseline, joined by a bar in the middle
+    for y in 7..=20 {
+        assert!(covered(2, y) && covered(11, y), "H at line {y}");
+        assert!(empty(0, y), "left of the H at line {y}");
+    }
+    assert!((2..=12).all(|x| covered(x, 13)));
+    assert!((4..=10).all(|x| empty(x, 9) && empty(x, 17)));
+    // the 'i': a dot, a gap and a stem
+    assert!(covered(17, 7) && empty(17, 9) && (11..=20).all(|y| covered(17, y)));
+    // nothing above the first line's letters or between its baseline and the second line's 'h'
+    assert!((0..width).all(|x| (0..=5).chain(21..=32).all(|y| empty(x, y))));
+    // the 'h' of "there" is as tall as the 'H', starting one line height further down
+    let line_height = font.line_height().round() as usize;
+    assert!((7..=20).all(|y| covered(9, y + line_height)));
+}
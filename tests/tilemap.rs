@@ -0,0 +1,96 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{FULL, SCREEN};
+use rust_wasm_test_game::{renderer_headless, world::{render_world_layers::Pixel, tilemap::{Tilemap, Tileset, CHUNK_SIZE}, world::Object::Objects::{LightObject, WorldObject}}};
+
+const RED: Pixel = Pixel::opaque(255, 0, 0);
+const GREEN: Pixel = Pixel::opaque(0, 255, 0);
+
+// three 2x2 tiles: red, green and one that is transparent except for its top left pixel, which is white
+fn tileset() -> Arc<Tileset> {
+    let mut rgba = vec![];
+    for y in 0..2 {
+        rgba.extend_from_slice(&[255, 0, 0, 255, 255, 0, 0, 255, 0, 255, 0, 255, 0, 255, 0, 255]);
+        rgba.extend_from_slice(if y == 0 { &[255, 255, 255, 255, 0, 0, 0, 0] } else { &[0, 0, 0, 0, 0, 0, 0, 0] });
+    }
+    Arc::new(Tileset::new(&rgba, 6, 2, 2, 2))
+}
+
+// a 40x40 tile (80x80 pixel) map of red tiles on a 64x64 screen, with a green tile in the top left corner
+fn map() -> WorldObject {
+    let mut tilemap = Tilemap::new(tileset(), 40, 40, 2);
+    tilemap.set_layer(0, vec![Some(0); 40 * 40]);
+    tilemap.set_tile(0, 0, 0, Some(1));
+    let mut object = WorldObject::new_tilemap(tilemap, 64, 64);
+    object.redraw();
+    object
+}
+
+fn draw_again(object: &mut WorldObject) {
+    (object.fns.draw_again)(&mut object.state, &std::time::Duration::ZERO);
+}
+
+#[test]
+fn only_visible_chunks_are_drawn() {
+    let mut object = map();
+    let layer = &object.state.layer;
+    assert_eq!((layer.pos_x, layer.pos_y, layer.pos_w, layer.pos_h), (0.0, 0.0, 64, 64));
+    assert_eq!(layer.line(0)[..4], [GREEN, GREEN, RED, RED]);
+    assert!(layer.opaque);
+    // a chunk is 32x32 pixels, so 2 of the 3 chunks in each direction are on the screen
+    assert_eq!(CHUNK_SIZE * 2, 32);
+    assert_eq!(object.tilemap().unwrap().chunks_drawn, 4);
+}
+
+#[test]
+fn changing_a_tile_only_draws_its_chunk_again() {
+    let mut object = map();
+    draw_again(&mut object);
+    assert_eq!(object.tilemap().unwrap().chunks_drawn, 4);
+    object.tilemap().unwrap().set_tile(0, 20, 3, Some(1));
+    assert_eq!(object.tilemap().unwrap().tile(0, 20, 3), Some(1));
+    draw_again(&mut object);
+    assert_eq!(object.tilemap().unwrap().chunks_drawn, 5);
+    assert_eq!(object.state.layer.line(6)[40], GREEN);
+    // setting a tile to what it already is doesn't change anything
+    object.tilemap().unwrap().set_tile(0, 20, 3, Some(1));
+    draw_again(&mut object);
+    assert_eq!(object.tilemap().unwrap().chunks_drawn, 5);
+}
+
+#[test]
+fn moving_the_map_draws_chunks_that_come_into_view() {
+    let mut object = map();
+    object.tilemap().unwrap().x = -20.4;
+    object.tilemap().unwrap().y = 30.0;
+    draw_again(&mut object);
+    let layer = &object.state.layer;
+    // the layer only covers the part of the map that is on the screen
+    assert_eq!((layer.pos_x, layer.pos_y, layer.pos_w, layer.pos_h), (0.0, 30.0, 60, 34));
+    assert_eq!(layer.line(0)[0], RED);
+    // map pixels 20 to 80 and 0 to 34: the third column of chunks is new
+    assert_eq!(object.tilemap().unwrap().chunks_drawn, 6);
+    // entirely off screen
+    object.tilemap().unwrap().x = 100.0;
+    draw_again(&mut object);
+    assert_eq!((object.state.layer.pos_w, object.state.layer.pos_h), (0, 0));
+}
+
+#[test]
+fn layers_are_drawn_on_top_of_each_other() {
+    let mut object = map();
+    object.tilemap().unwrap().set_tile(1, 1, 0, Some(2));
+    draw_again(&mut object);
+    let line = object.state.layer.line(0);
+    assert_eq!(line[..4], [GREEN, GREEN, Pixel::opaque(255, 255, 255), RED]);
+}
+
+#[test]
+fn tilemaps_render() {
+    let mut world_renderer = common::scene(32.0, SCREEN, vec![map()], vec![LightObject::new(0.0, 0.0, FULL, 64.0, 1.0)]);
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert_eq!(image.get_pixel(1, 1).0, [0, 254, 0, 255]);
+    assert_eq!(image.get_pixel(63, 63).0, [254, 0, 0, 255]);
+}
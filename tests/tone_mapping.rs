@@ -0,0 +1,105 @@
+mod common;
+
+use common::{FULL, SCREEN, WHITE};
+use rust_wasm_test_game::{renderer_headless, world::{render_world::{WorldRenderer, MAX_LIGHT, render_joiner::{ToneCurve, ToneMapping, multiply_factor}}, world::Object::Objects::{LightObject, OccluderObject}}};
+
+// a white 64x64px screen showing -32..32 on both axes, with that many lights of full brightness on top of each other in the centre
+fn scene(lights: usize, tone_mapping: ToneMapping) -> WorldRenderer {
+    let lights = (0..lights).map(|_| LightObject::new(0.0, 0.0, FULL, 8.0, 8.0)).collect();
+    let mut world_renderer = common::scene(32.0, SCREEN, vec![common::backdrop(WHITE, SCREEN)], lights);
+    world_renderer.tone_mapping = tone_mapping;
+    world_renderer
+}
+
+fn centre(world_renderer: &mut WorldRenderer) -> u8 {
+    renderer_headless::render_to_image(world_renderer).0.get_pixel(32, 32).0[0]
+}
+
+#[test]
+fn tone_curves() {
+    let clamp = ToneCurve::new(ToneMapping::Clamp, false);
+    // clamping shows light up to full brightness exactly like before
+    for value in 0..=255 {
+        for light in (0..=MAX_LIGHT).step_by(1009).chain([MAX_LIGHT]) {
+            assert_eq!(clamp.lit(value, light) as u32, multiply_factor(value, light).min(255));
+        }
+    }
+    let full = u16::MAX as u32;
+    for tone_mapping in [ToneMapping::Reinhard, ToneMapping::Aces] {
+        let curve = ToneCurve::new(tone_mapping, false);
+        assert_eq!(curve.lit(255, 0), 0, "{:?}", tone_mapping);
+        assert!((0..=MAX_LIGHT).step_by(257).map(|light| curve.lit(255, light)).collect::<Vec<_>>().windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", tone_mapping);
+        // brighter than full brightness is still brighter
+        assert!(curve.lit(255, full) < curve.lit(255, full * 4) && curve.lit(255, full * 4) < curve.lit(255, full * 16), "{:?}", tone_mapping);
+    }
+    assert_eq!(ToneMapping::Reinhard.map(1.0), 0.5);
+    assert!((ToneMapping::Aces.map(1.0) - 0.8).abs() < 0.01);
+    assert_eq!(ToneMapping::Aces.map(100.0), 1.0);
+}
+
+#[test]
+fn overlapping_lights_keep_getting_brighter() {
+    // clamped, a second light makes no difference
+    let clamped: Vec<u8> = [1, 2, 4].iter().map(|&lights| centre(&mut scene(lights, ToneMapping::Clamp))).collect();
+    assert_eq!(clamped, [254, 255, 255]);
+    for tone_mapping in [ToneMapping::Reinhard, ToneMapping::Aces] {
+        let mapped: Vec<u8> = [1, 2, 4].iter().map(|&lights| centre(&mut scene(lights, tone_mapping))).collect();
+        assert!(mapped[0] < mapped[1] && mapped[1] < mapped[2], "{:?}: {:?}", tone_mapping, mapped);
+    }
+}
+
+#[test]
+fn lights_are_added_up_to_max_light() {
+    // so many lights that they would overflow a u16 many times
+    let mut world_renderer = scene(200, ToneMapping::Reinhard);
+    let brightest = (ToneMapping::Reinhard.map(multiply_factor(255, MAX_LIGHT) as f32 / 255.0) * 255.0).round() as u8;
+    assert_eq!(centre(&mut world_renderer), brightest);
+}
+
+#[test]
+fn ambient_light_reaches_everything() {
+    let mut world_renderer = scene(0, ToneMapping::Clamp);
+    assert_eq!(centre(&mut world_renderer), 0);
+    world_renderer.world.ambient = (u16::MAX / 2, u16::MAX / 4, 0);
+    // even behind occluders
+    world_renderer.world.occluders.push(OccluderObject::Rectangle { x: -32.0, y: -32.0, w: 64.0, h: 64.0 });
+    let image = renderer_headless::render_to_image(&mut world_renderer).0;
+    assert!(image.pixels().all(|pixel| pixel.0 == [127, 63, 0, 255]), "{:?}", image.get_pixel(0, 0));
+    // and is added to the lights
+    world_renderer.world.lights_rendered.push(LightObject::new(0.0, 0.0, (u16::MAX / 2, 0, 0), 8.0, 8.0));
+    world_renderer.world.occluders.clear();
+    assert_eq!(centre(&mut world_renderer), 254);
+}
+
+#[test]
+fn linear_lighting_keeps_colours() {
+    let curve = ToneCurve::new(ToneMapping::Clamp, true);
+    let full = u16::MAX as u32;
+    // full brightness shows the colours like they are (up to rounding, like without linear lighting), no light is black
+    assert!((0..=255).all(|value| curve.lit(value, full).abs_diff(value) <= 1 && curve.lit(value, 0) == 0));
+    // half the light is half as bright in linear colour space, which is a lot brighter than half the srgb value
+    assert_eq!(curve.lit(255, full / 2), 187);
+    assert_eq!(ToneCurve::new(ToneMapping::Clamp, false).lit(255, full / 2), 127);
+    // the ratios between the channels in linear colour space stay the same, so the hue doesn't change in dim light
+    let to_linear = |value: u8| {
+        let value = value as f32 / 255.0;
+        if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+    };
+    let (r, g) = (230, 90);
+    for light in [full / 2, full / 5, full / 10] {
+        let ratio = to_linear(curve.lit(r, light)) / to_linear(curve.lit(g, light));
+        assert!((ratio / (to_linear(r) / to_linear(g)) - 1.0).abs() < 0.05, "{}: {}", light, ratio);
+    }
+}
+
+#[test]
+fn linear_lighting_in_a_scene() {
+    let mut world_renderer = scene(1, ToneMapping::Clamp);
+    world_renderer.world.lights_rendered[0].brightness = (u16::MAX / 2, u16::MAX / 2, u16::MAX / 2);
+    assert_eq!(centre(&mut world_renderer), 127);
+    world_renderer.linear_lighting = true;
+    assert_eq!(centre(&mut world_renderer), 187);
+    // the tables are made again when the tone mapping changes
+    world_renderer.tone_mapping = ToneMapping::Reinhard;
+    assert!(centre(&mut world_renderer) < 187);
+}
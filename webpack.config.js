@@ -15,6 +15,11 @@ module.exports = {
   },
   devServer: {
     contentBase: dist,
+    // lets the web workers of the thread pool share memory with the page, see index.html
+    headers: {
+      "Cross-Origin-Opener-Policy": "same-origin",
+      "Cross-Origin-Embedder-Policy": "require-corp",
+    },
   },
   plugins: [
     new CopyPlugin([